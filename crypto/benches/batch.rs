@@ -0,0 +1,98 @@
+// SPDX-FileCopyrightText: 2023 TriliTech <contact@trili.tech>
+// SPDX-License-Identifier: MIT
+
+//! Compares verifying signatures one by one, in parallel with the `parallel` feature, and
+//! as a batch.
+//!
+//! Run with `cargo bench -p tezos_crypto_rs --features parallel --bench batch`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use ed25519_dalek::Signer;
+use tezos_crypto_rs::batch::{verify_all, verify_signatures, BatchVerifier, SignedMessage};
+use tezos_crypto_rs::bls::bls_arb_keypair;
+use tezos_crypto_rs::hash::{PublicKeyEd25519, Signature};
+
+/// Size of the signed bytes of an endorsement.
+const MESSAGE_SIZE: usize = 80;
+
+fn message(i: usize) -> Vec<u8> {
+    (i as u32).to_be_bytes().repeat(MESSAGE_SIZE / 4)
+}
+
+fn bench_verification<PK>(c: &mut Criterion, name: &str, batch: &[SignedMessage<'_, PK>])
+where
+    PK: BatchVerifier + Sync,
+    PK::Signature: Sync,
+    PK::Error: Send + std::fmt::Debug,
+{
+    let mut group = c.benchmark_group(name);
+    group.sample_size(10);
+    let count = batch.len();
+    group.bench_with_input(BenchmarkId::new("sequential", count), batch, |b, batch| {
+        b.iter(|| {
+            batch
+                .iter()
+                .all(|(pk, sig, msg)| pk.verify_signature(sig, msg).unwrap())
+        })
+    });
+    group.bench_with_input(
+        BenchmarkId::new("verify_signatures", count),
+        batch,
+        |b, batch| b.iter(|| verify_signatures(batch)),
+    );
+    group.bench_with_input(BenchmarkId::new("verify_all", count), batch, |b, batch| {
+        b.iter(|| verify_all(batch).unwrap())
+    });
+    group.finish();
+}
+
+fn ed25519(c: &mut Criterion) {
+    const COUNT: usize = 1000;
+    let keys = (0..COUNT)
+        .map(|i| ed25519_dalek::SigningKey::from_bytes(&[i as u8; 32]))
+        .collect::<Vec<_>>();
+    let pks = keys
+        .iter()
+        .map(|sk| PublicKeyEd25519(sk.verifying_key().to_bytes().to_vec()))
+        .collect::<Vec<_>>();
+    let msgs = (0..COUNT).map(message).collect::<Vec<_>>();
+    let sigs = keys
+        .iter()
+        .zip(&msgs)
+        .map(|(sk, msg)| Signature(sk.sign(msg).to_bytes().to_vec()))
+        .collect::<Vec<_>>();
+    let batch = pks
+        .iter()
+        .zip(&sigs)
+        .zip(&msgs)
+        .map(|((pk, sig), msg)| (pk, sig, msg.as_slice()))
+        .collect::<Vec<_>>();
+    bench_verification(c, "ed25519", &batch);
+}
+
+fn bls(c: &mut Criterion) {
+    use proptest::strategy::{Strategy, ValueTree};
+    use proptest::test_runner::TestRunner;
+
+    const COUNT: usize = 100;
+    let mut runner = TestRunner::deterministic();
+    let keys = (0..COUNT)
+        .map(|_| bls_arb_keypair().new_tree(&mut runner).unwrap().current())
+        .collect::<Vec<_>>();
+    let msgs = (0..COUNT).map(message).collect::<Vec<_>>();
+    let sigs = keys
+        .iter()
+        .zip(&msgs)
+        .map(|((sk, _), msg)| sk.sign(msg).unwrap())
+        .collect::<Vec<_>>();
+    let batch = keys
+        .iter()
+        .zip(&sigs)
+        .zip(&msgs)
+        .map(|(((_, pk), sig), msg)| (pk, sig, msg.as_slice()))
+        .collect::<Vec<_>>();
+    bench_verification(c, "bls", &batch);
+}
+
+criterion_group!(benches, ed25519, bls);
+criterion_main!(benches);
@@ -0,0 +1,223 @@
+// SPDX-FileCopyrightText: 2023 TriliTech <contact@trili.tech>
+// SPDX-License-Identifier: MIT
+
+//! Verification of many signatures at once, such as a flood of consensus operations.
+//!
+//! [verify_all] checks Ed25519 and BLS signatures as a batch, which is cheaper than
+//! checking them one by one. Other signatures, and batches that fail, are verified one by
+//! one, so that errors are the same as those of
+//! [PublicKeySignatureVerifier::verify_signature].
+//!
+//! With the `parallel` feature, signatures verified one by one are verified in parallel
+//! on the [rayon] global thread pool.
+//!
+//! [rayon]: <https://crates.io/crates/rayon>
+
+use crate::hash::{
+    BlsSignature, PublicKeyBls, PublicKeyEd25519, PublicKeyP256, PublicKeySecp256k1,
+};
+use crate::PublicKeySignatureVerifier;
+
+/// Public key, signature and signed bytes.
+pub type SignedMessage<'a, PK> = (
+    &'a PK,
+    &'a <PK as PublicKeySignatureVerifier>::Signature,
+    &'a [u8],
+);
+
+/// Public keys whose signatures can be verified together.
+pub trait BatchVerifier: PublicKeySignatureVerifier + Sized {
+    /// Verifies that all the signed messages are valid.
+    fn verify_batch(messages: &[SignedMessage<'_, Self>]) -> Result<bool, Self::Error>;
+}
+
+fn verify<PK: PublicKeySignatureVerifier>(
+    (pk, signature, bytes): &SignedMessage<'_, PK>,
+) -> Result<bool, PK::Error> {
+    pk.verify_signature(signature, bytes)
+}
+
+/// Verifies each of the signed messages.
+///
+/// Results are in the same order as `messages`.
+pub fn verify_signatures<PK>(messages: &[SignedMessage<'_, PK>]) -> Vec<Result<bool, PK::Error>>
+where
+    PK: PublicKeySignatureVerifier + Sync,
+    PK::Signature: Sync,
+    PK::Error: Send,
+{
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        messages.par_iter().map(verify).collect()
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        messages.iter().map(verify).collect()
+    }
+}
+
+/// Verifies that all the signed messages are valid, as a batch where supported.
+///
+/// Fails with the error of one of the messages whose verification failed, if any.
+pub fn verify_all<PK: BatchVerifier>(
+    messages: &[SignedMessage<'_, PK>],
+) -> Result<bool, PK::Error> {
+    PK::verify_batch(messages)
+}
+
+/// Verifies that all the signed messages are valid, one by one.
+fn verify_each<PK>(messages: &[SignedMessage<'_, PK>]) -> Result<bool, PK::Error>
+where
+    PK: PublicKeySignatureVerifier + Sync,
+    PK::Signature: Sync,
+    PK::Error: Send,
+{
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        messages
+            .par_iter()
+            .map(verify)
+            .try_fold(|| true, |valid, res| res.map(|v| valid && v))
+            .try_reduce(|| true, |a, b| Ok(a && b))
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        messages
+            .iter()
+            .try_fold(true, |valid, message| verify(message).map(|v| valid && v))
+    }
+}
+
+impl BatchVerifier for PublicKeyEd25519 {
+    /// Verifies the signatures with the batch equation of `ed25519_dalek::verify_batch`.
+    ///
+    /// Unlike the strict verification of single signatures, the batch equation may accept
+    /// signatures whose `R` has a torsion component. Weak public keys are rejected.
+    fn verify_batch(messages: &[SignedMessage<'_, Self>]) -> Result<bool, Self::Error> {
+        let batch = messages
+            .iter()
+            .map(|(pk, signature, bytes)| {
+                let pk = ed25519_dalek::VerifyingKey::try_from(*pk).ok()?;
+                let signature = signature.0.as_slice().try_into().ok()?;
+                (!pk.is_weak())
+                    .then(|| (pk, ed25519_dalek::Signature::from_bytes(signature), *bytes))
+            })
+            .collect::<Option<Vec<_>>>();
+        if let Some(batch) = batch {
+            let keys = batch.iter().map(|(pk, _, _)| *pk).collect::<Vec<_>>();
+            let signatures = batch.iter().map(|(_, sig, _)| *sig).collect::<Vec<_>>();
+            let bytes = batch.iter().map(|(_, _, bytes)| *bytes).collect::<Vec<_>>();
+            if ed25519_dalek::verify_batch(&bytes, &signatures, &keys).is_ok() {
+                return Ok(true);
+            }
+        }
+        verify_each(messages)
+    }
+}
+
+impl BatchVerifier for PublicKeyBls {
+    /// Verifies the aggregate of the signatures against all the messages.
+    ///
+    /// This proves that each key signed its message in aggregate only: invalid signatures
+    /// may be accepted if their errors cancel out in the aggregate. Use
+    /// [verify_signatures] to check each signature on its own.
+    fn verify_batch(messages: &[SignedMessage<'_, Self>]) -> Result<bool, Self::Error> {
+        let signatures = messages
+            .iter()
+            .map(|(_, signature, _)| *signature)
+            .collect::<Vec<_>>();
+        let valid = BlsSignature::aggregate_sigs(&signatures).and_then(|aggregate| {
+            aggregate.aggregate_verify(&mut messages.iter().map(|(pk, _, bytes)| (*bytes, *pk)))
+        });
+        match valid {
+            Ok(true) => Ok(true),
+            _ => verify_each(messages),
+        }
+    }
+}
+
+macro_rules! verify_each {
+    ($($pk:ty),*) => {
+        $(
+            impl BatchVerifier for $pk {
+                /// Verifies the signatures one by one.
+                fn verify_batch(messages: &[SignedMessage<'_, Self>]) -> Result<bool, Self::Error> {
+                    verify_each(messages)
+                }
+            }
+        )*
+    };
+}
+
+verify_each!(PublicKeySecp256k1, PublicKeyP256);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bls::bls_arb_keypair;
+    use crate::hash::{PublicKeyEd25519, Signature};
+    use ed25519_dalek::Signer;
+    use proptest::prelude::*;
+
+    #[test]
+    fn verify_ed25519() {
+        let keys = (0..8u8)
+            .map(|i| ed25519_dalek::SigningKey::from_bytes(&[i; 32]))
+            .collect::<Vec<_>>();
+        let pks = keys
+            .iter()
+            .map(|sk| PublicKeyEd25519(sk.verifying_key().to_bytes().to_vec()))
+            .collect::<Vec<_>>();
+        let msgs = (0..8u8).map(|i| vec![i; 32]).collect::<Vec<_>>();
+        let mut sigs = keys
+            .iter()
+            .zip(&msgs)
+            .map(|(sk, msg)| Signature(sk.sign(msg).to_bytes().to_vec()))
+            .collect::<Vec<_>>();
+
+        let batch = |sigs: &[Signature]| {
+            let batch = pks
+                .iter()
+                .zip(sigs)
+                .zip(&msgs)
+                .map(|((pk, sig), msg)| (pk, sig, msg.as_slice()))
+                .collect::<Vec<_>>();
+            (verify_signatures(&batch), verify_all(&batch))
+        };
+
+        let (results, all) = batch(&sigs);
+        assert!(results.iter().all(|res| matches!(res, Ok(true))));
+        assert!(matches!(all, Ok(true)));
+
+        // signatures of other messages
+        sigs.swap(2, 5);
+        let (results, all) = batch(&sigs);
+        for (i, res) in results.iter().enumerate() {
+            assert_eq!(res.is_ok(), i != 2 && i != 5, "message {}", i);
+        }
+        assert!(all.is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn verify_bls(keys in proptest::collection::vec(bls_arb_keypair(), 1..4), msg in any::<Vec<u8>>()) {
+            let sigs = keys
+                .iter()
+                .map(|(sk, _)| sk.sign(&msg).unwrap())
+                .collect::<Vec<_>>();
+            let mut batch = keys
+                .iter()
+                .zip(&sigs)
+                .map(|((_, pk), sig)| (pk, sig, msg.as_slice()))
+                .collect::<Vec<_>>();
+            prop_assert!(matches!(verify_all(&batch), Ok(true)));
+
+            let other_msg = [msg.as_slice(), &[0]].concat();
+            batch[0].2 = &other_msg;
+            prop_assert!(matches!(verify_all(&batch), Ok(false)));
+            prop_assert!(matches!(verify_signatures(&batch)[0], Ok(false)));
+        }
+    }
+}
@@ -9,6 +9,7 @@ use crate::hash::ContractTz4Hash;
 use crate::hash::PublicKeyBls;
 use crate::hash::SecretKeyBls;
 use crate::CryptoError;
+use crate::PublicKeySignatureVerifier;
 use crate::PublicKeyWithHash;
 use blst::min_pk;
 use blst::min_pk::{AggregateSignature, SecretKey};
@@ -113,6 +114,22 @@ impl BlsSignature {
     }
 }
 
+impl PublicKeySignatureVerifier for PublicKeyBls {
+    type Signature = BlsSignature;
+    type Error = CryptoError;
+
+    /// Verifies the correctness of `bytes` signed by BLS as the `signature`.
+    ///
+    /// Like Tezos_crypto, this uses the Aug suite: the public key is prepended to `bytes`.
+    fn verify_signature(
+        &self,
+        signature: &BlsSignature,
+        bytes: &[u8],
+    ) -> Result<bool, Self::Error> {
+        signature.aggregate_verify(&mut std::iter::once((bytes, self)))
+    }
+}
+
 impl TryFrom<&BlsSignature> for min_pk::Signature {
     type Error = CryptoError;
 
@@ -466,6 +483,17 @@ mod tests {
           assert!(matches!(res, Ok(true)));
       }
 
+      #[test]
+      fn verify_signature_with_public_key((sk, pk) in bls_arb_keypair(), msg in any::<Vec<u8>>()) {
+          let sig = sk.sign(msg.as_slice()).unwrap();
+
+          assert!(matches!(pk.verify_signature(&sig, &msg), Ok(true)));
+
+          let mut other_msg = msg;
+          other_msg.push(0);
+          assert!(matches!(pk.verify_signature(&sig, &other_msg), Ok(false)));
+      }
+
       #[test]
       fn verify_aggregate_signature(
           (fst_sk, fst_pk) in bls_arb_keypair(),
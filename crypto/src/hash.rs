@@ -10,6 +10,7 @@ use crate::{
     CryptoError, PublicKeySignatureVerifier, PublicKeyWithHash,
 };
 use serde::{Deserialize, Serialize};
+use subtle::ConstantTimeEq;
 use thiserror::Error;
 use zeroize::Zeroize;
 
@@ -36,7 +37,7 @@ mod prefix_bytes {
     pub const PUBLIC_KEY_SECP256K1: [u8; 4] = [3, 254, 226, 86];
     pub const PUBLIC_KEY_P256: [u8; 4] = [3, 178, 139, 127];
     pub const PUBLIC_KEY_BLS: [u8; 4] = [6, 149, 135, 204];
-    pub const SEED_ED25519: [u8; 4] = [43, 246, 78, 7];
+    pub const SEED_ED25519: [u8; 4] = [13, 15, 58, 7];
     pub const SECRET_KEY_ED25519: [u8; 4] = [43, 246, 78, 7];
     pub const SECRET_KEY_BLS: [u8; 4] = [3, 150, 192, 40];
     pub const ED22519_SIGNATURE_HASH: [u8; 5] = [9, 245, 205, 134, 18];
@@ -45,6 +46,7 @@ mod prefix_bytes {
     pub const NONCE_HASH: [u8; 3] = [69, 220, 169];
     pub const OPERATION_LIST_HASH: [u8; 2] = [133, 233];
     pub const SMART_ROLLUP_HASH: [u8; 3] = [6, 124, 117];
+    pub const SCRIPT_EXPR_HASH: [u8; 4] = [13, 44, 64, 27];
 }
 
 pub type Hash = Vec<u8>;
@@ -58,6 +60,11 @@ pub trait HashTrait: Into<Hash> + AsRef<Hash> {
         Self::hash_type().size()
     }
 
+    /// Returns the type of this hash value.
+    fn kind(&self) -> HashType {
+        Self::hash_type()
+    }
+
     /// Tries to create this hash from the `bytes`.
     fn try_from_bytes(bytes: &[u8]) -> Result<Self, FromBytesError>;
 
@@ -83,6 +90,28 @@ macro_rules! define_hash {
         #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
         pub struct $name(pub Hash);
 
+        define_hash!(@impl $name);
+    };
+    ($name:ident, constant_time_eq) => {
+        /// Compared in constant time, so that comparisons do not leak the contents through timing.
+        #[derive(Clone, Eq, PartialOrd, Ord)]
+        pub struct $name(pub Hash);
+
+        impl PartialEq for $name {
+            fn eq(&self, other: &Self) -> bool {
+                self.0.as_slice().ct_eq(other.0.as_slice()).into()
+            }
+        }
+
+        impl std::hash::Hash for $name {
+            fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                self.0.hash(state)
+            }
+        }
+
+        define_hash!(@impl $name);
+    };
+    (@impl $name:ident) => {
         impl $name {
             fn from_bytes(data: &[u8]) -> Result<Self, FromBytesError> {
                 if data.len() == HashType::$name.size() {
@@ -128,6 +157,22 @@ macro_rules! define_hash {
             }
         }
 
+        #[cfg(feature = "proptest")]
+        impl proptest::arbitrary::Arbitrary for $name {
+            type Parameters = ();
+            type Strategy = proptest::strategy::BoxedStrategy<Self>;
+
+            fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+                use proptest::strategy::Strategy;
+                proptest::collection::vec(
+                    proptest::arbitrary::any::<u8>(),
+                    HashType::$name.size(),
+                )
+                .prop_map($name)
+                .boxed()
+            }
+        }
+
         impl ::std::fmt::Debug for $name {
             fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
                 // TODO - TE-373: with b58 this could be done without the need
@@ -303,15 +348,16 @@ define_hash!(PublicKeyEd25519);
 define_hash!(PublicKeySecp256k1);
 define_hash!(PublicKeyP256);
 define_hash!(PublicKeyBls);
-define_hash!(SeedEd25519);
-define_hash!(SecretKeyEd25519);
-define_hash!(SecretKeyBls);
-define_hash!(Ed25519Signature);
-define_hash!(Signature);
-define_hash!(BlsSignature);
+define_hash!(SeedEd25519, constant_time_eq);
+define_hash!(SecretKeyEd25519, constant_time_eq);
+define_hash!(SecretKeyBls, constant_time_eq);
+define_hash!(Ed25519Signature, constant_time_eq);
+define_hash!(Signature, constant_time_eq);
+define_hash!(BlsSignature, constant_time_eq);
 define_hash!(NonceHash);
 define_hash!(OperationListHash);
 define_hash!(SmartRollupHash);
+define_hash!(ScriptExprHash);
 
 /// Note: see Tezos ocaml lib_crypto/base58.ml
 #[derive(Debug, Copy, Clone, PartialEq, strum_macros::AsRefStr)]
@@ -374,6 +420,8 @@ pub enum HashType {
     OperationListHash,
     // "\006\124\117" (* sr1(36) *)
     SmartRollupHash,
+    // "\013\044\064\027" (* expr(54) *)
+    ScriptExprHash,
 }
 
 impl HashType {
@@ -410,6 +458,7 @@ impl HashType {
             HashType::NonceHash => &NONCE_HASH,
             HashType::OperationListHash => &OPERATION_LIST_HASH,
             HashType::SmartRollupHash => &SMART_ROLLUP_HASH,
+            HashType::ScriptExprHash => &SCRIPT_EXPR_HASH,
         }
     }
 
@@ -428,7 +477,8 @@ impl HashType {
             | HashType::OperationMetadataListListHash
             | HashType::PublicKeyEd25519
             | HashType::NonceHash
-            | HashType::OperationListHash => 32,
+            | HashType::OperationListHash
+            | HashType::ScriptExprHash => 32,
             HashType::CryptoboxPublicKeyHash => 16,
             HashType::ContractKt1Hash
             | HashType::ContractTz1Hash
@@ -490,6 +540,9 @@ impl HashType {
                 actual: hash.len(),
             });
         }
+        if !hash.starts_with(self.base58check_prefix()) {
+            return Err(FromBase58CheckError::IncorrectBase58Prefix);
+        }
         // prefix is not present in a binary representation
         hash.drain(0..self.base58check_prefix().len());
         Ok(hash)
@@ -573,6 +626,21 @@ impl TryFrom<PublicKeyBls> for ContractTz4Hash {
     }
 }
 
+impl From<Ed25519Signature> for Signature {
+    fn from(source: Ed25519Signature) -> Self {
+        Signature(source.0)
+    }
+}
+
+impl TryFrom<Signature> for Ed25519Signature {
+    type Error = FromBytesError;
+
+    /// Interprets the generic signature as an Ed25519 one; only its size can be checked.
+    fn try_from(source: Signature) -> Result<Self, Self::Error> {
+        Self::from_vec(source.0)
+    }
+}
+
 impl TryFrom<&PublicKeyEd25519> for ed25519_dalek::VerifyingKey {
     type Error = FromBytesError;
 
@@ -1069,6 +1137,46 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_b58_to_hash_incorrect_prefix() -> Result<(), anyhow::Error> {
+        let block_hash = "BKyQ9EofHrgaZKENioHyP4FZNsTmiSEcVmcghgzCC9cGhE7oCET";
+        assert!(matches!(
+            HashType::OperationHash.b58check_to_hash(block_hash),
+            Err(FromBase58CheckError::IncorrectBase58Prefix)
+        ));
+        assert!(matches!(
+            ContextHash::from_b58check(block_hash),
+            Err(FromBase58CheckError::IncorrectBase58Prefix)
+        ));
+        assert!(BlockHash::from_b58check(block_hash).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash_kind() -> Result<(), anyhow::Error> {
+        let hash = BlockHash::from_b58check("BKyQ9EofHrgaZKENioHyP4FZNsTmiSEcVmcghgzCC9cGhE7oCET")?;
+        assert_eq!(hash.kind(), HashType::BlockHash);
+        assert_eq!(Signature(vec![0; 64]).kind(), HashType::Signature);
+        Ok(())
+    }
+
+    #[test]
+    fn test_signature_conversions() -> Result<(), anyhow::Error> {
+        let ed25519 = Ed25519Signature(vec![1; 64]);
+        let signature = Signature::from(ed25519.clone());
+        assert_eq!(signature.0, ed25519.0);
+        assert_eq!(Ed25519Signature::try_from(signature)?, ed25519);
+        assert!(Ed25519Signature::try_from(Signature(vec![1; 32])).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert_eq!(Signature(vec![1; 64]), Signature(vec![1; 64]));
+        assert_ne!(Signature(vec![1; 64]), Signature(vec![2; 64]));
+        assert_ne!(Signature(vec![1; 64]), Signature(vec![1; 63]));
+    }
+
     #[test]
     fn test_b85_to_signature_hash() -> Result<(), anyhow::Error> {
         let encoded = "sigbQ5ZNvkjvGssJgoAnUAfY4Wvvg3QZqawBYB1j1VDBNTMBAALnCzRHWzer34bnfmzgHg3EvwdzQKdxgSghB897cono6gbQ";
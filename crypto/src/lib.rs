@@ -9,6 +9,7 @@ use thiserror::Error;
 #[macro_use]
 pub mod blake2b;
 pub mod base58;
+pub mod batch;
 pub mod bls;
 #[macro_use]
 pub mod hash;
@@ -0,0 +1,448 @@
+// SPDX-FileCopyrightText: 2023 TriliTech <contact@trili.tech>
+// SPDX-License-Identifier: MIT
+
+//! Generation of `proptest` strategies and round-trip tests from encodings.
+
+use crate::encoding::*;
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote, quote_spanned};
+use syn::spanned::Spanned;
+
+/// Maximal length of generated lists, strings and byte sequences.
+const MAX_LEN: usize = 8;
+
+/// Maximal nesting depth of generated values of recursive types.
+const MAX_DEPTH: u32 = 4;
+
+/// Strategies generated for references of a recursive type to itself.
+#[derive(Clone, Copy)]
+enum Recursion<'a> {
+    /// The type is not recursive.
+    Off,
+    /// No references, for the leaves of recursive values.
+    Leaf(&'a syn::Ident),
+    /// References generated by the `inner` strategy of `prop_recursive`.
+    Inner(&'a syn::Ident),
+}
+
+/// Generates a `#[cfg(test)]` `Arbitrary` implementation for the data, and a property
+/// test checking that encoding and decoding arbitrary values gives them back.
+pub fn generate_roundtrip_test_for_data(data: &DataWithEncoding) -> TokenStream {
+    let name = data.name;
+    let strategy = generate_data_strategy(data);
+    let test_mod = format_ident!("__{}_roundtrip_test", name);
+    quote_spanned! {
+        data.name.span()=>
+        #[cfg(test)]
+        #[allow(clippy::unnecessary_cast)]
+        #[allow(clippy::useless_conversion)]
+        impl proptest::arbitrary::Arbitrary for #name {
+            type Parameters = ();
+            type Strategy = proptest::strategy::BoxedStrategy<Self>;
+
+            fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+                use proptest::strategy::Strategy;
+                (#strategy).boxed()
+            }
+        }
+
+        #[cfg(test)]
+        #[allow(non_snake_case)]
+        #[allow(clippy::useless_conversion)]
+        mod #test_mod {
+            use super::*;
+
+            proptest::proptest! {
+                #[test]
+                fn nom_read_bin_write_roundtrip(value in proptest::arbitrary::any::<#name>()) {
+                    let mut encoded = Vec::new();
+                    // values exceeding encoding bounds cannot be written
+                    proptest::prop_assume!(
+                        tezos_data_encoding::enc::BinWriter::bin_write(&value, &mut encoded).is_ok()
+                    );
+                    let (rest, decoded) =
+                        <#name as tezos_data_encoding::nom::NomReader>::nom_read(&encoded)
+                            .map_err(|e| proptest::test_runner::TestCaseError::fail(format!("{:?}", e)))?;
+                    proptest::prop_assert!(rest.is_empty());
+                    proptest::prop_assert_eq!(decoded, value);
+                }
+            }
+        }
+    }
+}
+
+/// Generates the strategy of the data, built with `prop_recursive` for recursive types so
+/// that generated values do not exceed their `max_depth`.
+fn generate_data_strategy(data: &DataWithEncoding) -> TokenStream {
+    let name = data.name;
+    if !is_recursive(&data.encoding, name) {
+        return generate_strategy(&data.encoding, Recursion::Off);
+    }
+    let depth = match max_depth(&data.encoding) {
+        // `prop_recursive` nests up to `depth` values above the leaves
+        Some(max) => quote!(core::cmp::min((#max as u32).saturating_sub(1), #MAX_DEPTH)),
+        None => quote!(#MAX_DEPTH),
+    };
+    let leaf = generate_strategy(&data.encoding, Recursion::Leaf(name));
+    let recursive = generate_strategy(&data.encoding, Recursion::Inner(name));
+    quote_spanned! {
+        name.span()=> (#leaf).prop_recursive(#depth, 64, #MAX_LEN as u32, |inner| #recursive)
+    }
+}
+
+fn generate_strategy(encoding: &Encoding, recursion: Recursion) -> TokenStream {
+    generate_bounded_strategy(encoding, None, recursion)
+}
+
+/// Generates a strategy for values of the encoding, whose encoded size fits `budget`
+/// bytes if given.
+///
+/// Lists are shortened to fit the budget when the size of their elements is known, and
+/// the remaining out-of-bound values are rejected by the round-trip test.
+fn generate_bounded_strategy(
+    encoding: &Encoding,
+    budget: Option<&TokenStream>,
+    recursion: Recursion,
+) -> TokenStream {
+    match encoding {
+        Encoding::Unit => unreachable!(),
+        Encoding::Primitive(primitive, span) => generate_primitive_strategy(*primitive, *span),
+        Encoding::Bytes(span) => {
+            let max = min_budget(quote!(#MAX_LEN), budget);
+            generate_bytes_strategy(quote!(0..=#max), *span)
+        }
+        Encoding::Path(path) => generate_path_strategy(path, recursion),
+        Encoding::Struct(encoding) => generate_struct_strategy(encoding, recursion),
+        Encoding::Enum(encoding) => generate_enum_strategy(encoding, recursion),
+        Encoding::String(size, span) => {
+            let budget = budget.map(|budget| quote!(usize::saturating_sub(#budget, 4)));
+            generate_string_strategy(size, budget.as_ref(), *span)
+        }
+        Encoding::OptionField(encoding, span) if recursion.is_leaf_of(encoding) => {
+            quote_spanned!(*span=> proptest::strategy::LazyJust::new(|| None))
+        }
+        Encoding::OptionField(encoding, span) => {
+            let budget = budget.map(|budget| quote!(usize::saturating_sub(#budget, 1)));
+            let strategy = generate_bounded_strategy(encoding, budget.as_ref(), recursion);
+            quote_spanned!(*span=> proptest::option::of(#strategy))
+        }
+        Encoding::List(_, encoding, span) if recursion.is_leaf_of(encoding) => {
+            quote_spanned!(*span=> proptest::strategy::LazyJust::new(Vec::new))
+        }
+        Encoding::List(size, encoding, span) => {
+            let strategy = generate_bounded_strategy(encoding, budget, recursion);
+            let max = size.as_ref().map_or_else(
+                || quote!(#MAX_LEN),
+                |size| quote!(core::cmp::min(#size, #MAX_LEN)),
+            );
+            let max = match (budget, max_size(encoding)) {
+                (Some(budget), Some(element)) => {
+                    quote!(core::cmp::min(#max, (#budget) / core::cmp::max(#element, 1)))
+                }
+                _ => max,
+            };
+            quote_spanned!(*span=> proptest::collection::vec(#strategy, 0..=#max))
+        }
+        Encoding::Sized(size, encoding, span) => match encoding.as_ref() {
+            Encoding::Bytes(_) => generate_bytes_strategy(quote!(#size), *span),
+            _ => generate_bounded_strategy(encoding, Some(&quote!(#size)), recursion),
+        },
+        Encoding::Bounded(size, encoding, _) => {
+            let budget = min_budget(quote!(#size), budget);
+            generate_bounded_strategy(encoding, Some(&budget), recursion)
+        }
+        Encoding::ShortDynamic(encoding, _) => {
+            let budget = budget.map(|budget| quote!(usize::saturating_sub(#budget, 1)));
+            let budget = min_budget(quote!(255usize), budget.as_ref());
+            generate_bounded_strategy(encoding, Some(&budget), recursion)
+        }
+        Encoding::Dynamic(size, encoding, _) => {
+            let budget = budget.map(|budget| quote!(usize::saturating_sub(#budget, 4)));
+            let budget = match size {
+                Some(size) => Some(min_budget(quote!(#size), budget.as_ref())),
+                None => budget,
+            };
+            generate_bounded_strategy(encoding, budget.as_ref(), recursion)
+        }
+        Encoding::BoundedDepth(_, encoding, _) => {
+            generate_bounded_strategy(encoding, budget, recursion)
+        }
+        Encoding::Zarith(span) => {
+            quote_spanned!(*span=> proptest::arbitrary::any::<tezos_data_encoding::types::Zarith>().prop_map(|n| n.0.into()))
+        }
+        Encoding::MuTez(span) => {
+            quote_spanned!(*span=> proptest::arbitrary::any::<tezos_data_encoding::types::Mutez>().prop_map(|n| n.0.into()))
+        }
+    }
+}
+
+impl Recursion<'_> {
+    /// Whether values of the encoding are replaced by empty ones in the leaves.
+    fn is_leaf_of(self, encoding: &Encoding) -> bool {
+        match self {
+            Recursion::Leaf(name) => is_recursive(encoding, name),
+            _ => false,
+        }
+    }
+}
+
+/// Whether the encoding refers to the type `name`.
+fn is_recursive(encoding: &Encoding, name: &syn::Ident) -> bool {
+    match encoding {
+        Encoding::Path(path) => refers_to(quote!(#path), name),
+        Encoding::Struct(encoding) => encoding
+            .fields
+            .iter()
+            .filter_map(FieldEncoding::encoding)
+            .any(|encoding| is_recursive(encoding, name)),
+        Encoding::Enum(encoding) => encoding
+            .tags
+            .iter()
+            .any(|tag| is_recursive(&tag.encoding, name)),
+        Encoding::OptionField(encoding, _)
+        | Encoding::List(_, encoding, _)
+        | Encoding::Sized(_, encoding, _)
+        | Encoding::Bounded(_, encoding, _)
+        | Encoding::ShortDynamic(encoding, _)
+        | Encoding::Dynamic(_, encoding, _)
+        | Encoding::BoundedDepth(_, encoding, _) => is_recursive(encoding, name),
+        Encoding::Unit
+        | Encoding::Primitive(_, _)
+        | Encoding::Bytes(_)
+        | Encoding::String(_, _)
+        | Encoding::Zarith(_)
+        | Encoding::MuTez(_) => false,
+    }
+}
+
+/// If the path is `Self` or `name`, possibly in a `Box`, returns whether it is boxed.
+fn self_reference(path: &syn::Path, name: &syn::Ident) -> Option<bool> {
+    let segment = path.segments.last()?;
+    match &segment.arguments {
+        syn::PathArguments::None => {
+            (segment.ident == *name || segment.ident == "Self").then(|| false)
+        }
+        syn::PathArguments::AngleBracketed(args) if segment.ident == "Box" => {
+            match args.args.first() {
+                Some(syn::GenericArgument::Type(syn::Type::Path(ty)))
+                    if args.args.len() == 1 && ty.qself.is_none() =>
+                {
+                    self_reference(&ty.path, name)
+                        .filter(|boxed| !boxed)
+                        .map(|_| true)
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Whether the tokens contain `Self` or `name`.
+fn refers_to(tokens: TokenStream, name: &syn::Ident) -> bool {
+    tokens.into_iter().any(|token| match token {
+        proc_macro2::TokenTree::Ident(ident) => ident == *name || ident == "Self",
+        proc_macro2::TokenTree::Group(group) => refers_to(group.stream(), name),
+        _ => false,
+    })
+}
+
+/// The `max_depth` of the data encoding, if any.
+fn max_depth<'a>(encoding: &'a Encoding) -> Option<&'a syn::Expr> {
+    match encoding {
+        Encoding::BoundedDepth(max, _, _) => Some(max),
+        Encoding::Sized(_, encoding, _)
+        | Encoding::Bounded(_, encoding, _)
+        | Encoding::ShortDynamic(encoding, _)
+        | Encoding::Dynamic(_, encoding, _) => max_depth(encoding),
+        _ => None,
+    }
+}
+
+fn min_budget(max: TokenStream, budget: Option<&TokenStream>) -> TokenStream {
+    match budget {
+        Some(budget) => quote!(core::cmp::min(#max, #budget)),
+        None => max,
+    }
+}
+
+/// Maximal encoded size of values generated for the encoding, if it is known statically.
+fn max_size(encoding: &Encoding) -> Option<TokenStream> {
+    match encoding {
+        Encoding::Unit => Some(quote!(0usize)),
+        Encoding::Primitive(primitive, _) => {
+            let size: usize = match primitive {
+                PrimitiveEncoding::Int8 | PrimitiveEncoding::Uint8 | PrimitiveEncoding::Bool => 1,
+                PrimitiveEncoding::Int16 | PrimitiveEncoding::Uint16 => 2,
+                PrimitiveEncoding::Int31 | PrimitiveEncoding::Int32 | PrimitiveEncoding::Uint32 => {
+                    4
+                }
+                PrimitiveEncoding::Int64
+                | PrimitiveEncoding::Float
+                | PrimitiveEncoding::Timestamp => 8,
+            };
+            Some(quote!(#size))
+        }
+        Encoding::Bytes(_) => Some(quote!(#MAX_LEN)),
+        Encoding::String(_, _) => Some(quote!(4 + #MAX_LEN)),
+        Encoding::OptionField(encoding, _) => max_size(encoding).map(|size| quote!(1 + #size)),
+        Encoding::List(_, encoding, _) => max_size(encoding).map(|size| quote!(#MAX_LEN * #size)),
+        Encoding::Sized(size, _, _) => Some(quote!(#size)),
+        Encoding::Bounded(_, encoding, _) | Encoding::BoundedDepth(_, encoding, _) => {
+            max_size(encoding)
+        }
+        Encoding::ShortDynamic(encoding, _) => max_size(encoding).map(|size| quote!(1 + #size)),
+        Encoding::Dynamic(_, encoding, _) => max_size(encoding).map(|size| quote!(4 + #size)),
+        Encoding::Path(_)
+        | Encoding::Struct(_)
+        | Encoding::Enum(_)
+        | Encoding::Zarith(_)
+        | Encoding::MuTez(_) => None,
+    }
+}
+
+fn generate_path_strategy(path: &syn::Path, recursion: Recursion) -> TokenStream {
+    let name = match recursion {
+        Recursion::Leaf(name) | Recursion::Inner(name) if refers_to(quote!(#path), name) => name,
+        _ => return quote_spanned!(path.span()=> proptest::arbitrary::any::<#path>()),
+    };
+    match (recursion, self_reference(path, name)) {
+        (Recursion::Inner(_), Some(false)) => quote_spanned!(path.span()=> inner.clone()),
+        (Recursion::Inner(_), Some(true)) => {
+            quote_spanned!(path.span()=> inner.clone().prop_map(Box::new))
+        }
+        (Recursion::Inner(_), None) => quote_spanned! {
+            path.span()=> compile_error!("`roundtrip_test` only supports recursive references as `Self` or `Box<Self>`")
+        },
+        _ => quote_spanned! {
+            path.span()=> compile_error!(
+                "`roundtrip_test` needs recursive references in an `Option`, a list or an enum variant"
+            )
+        },
+    }
+}
+
+fn generate_primitive_strategy(kind: PrimitiveEncoding, span: Span) -> TokenStream {
+    match kind {
+        PrimitiveEncoding::Int31 => quote_spanned!(span=> (-(1i32 << 30))..(1i32 << 30)),
+        PrimitiveEncoding::Float => {
+            quote_spanned!(span=> proptest::num::f64::NORMAL | proptest::num::f64::ZERO)
+        }
+        PrimitiveEncoding::Int8 => quote_spanned!(span=> proptest::arbitrary::any::<i8>()),
+        PrimitiveEncoding::Uint8 => quote_spanned!(span=> proptest::arbitrary::any::<u8>()),
+        _ => {
+            let ty = syn::Ident::new(get_primitive_number_mapping(kind).unwrap(), span);
+            quote_spanned!(span=> proptest::arbitrary::any::<#ty>())
+        }
+    }
+}
+
+fn generate_bytes_strategy(size: TokenStream, span: Span) -> TokenStream {
+    quote_spanned!(span=> proptest::collection::vec(proptest::arbitrary::any::<u8>(), #size))
+}
+
+fn generate_string_strategy(
+    size: &Option<syn::Expr>,
+    budget: Option<&TokenStream>,
+    span: Span,
+) -> TokenStream {
+    let max = size.as_ref().map_or_else(
+        || quote!(#MAX_LEN),
+        |size| quote!(core::cmp::min(#size, #MAX_LEN)),
+    );
+    let max = min_budget(max, budget);
+    // ASCII only, so that the bound on bytes is also a bound on chars
+    quote_spanned! {
+        span=> proptest::collection::vec(0x20u8..0x7f, 0..=#max)
+            .prop_map(|bytes| String::from_utf8(bytes).unwrap())
+    }
+}
+
+fn generate_struct_strategy(encoding: &StructEncoding, recursion: Recursion) -> TokenStream {
+    let name = encoding.name;
+    let encoded = encoding
+        .fields
+        .iter()
+        .filter_map(|field| match &field.kind {
+            FieldKind::Encoded(encoded) => Some((field.name, &encoded.encoding)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let skipped = encoding
+        .fields
+        .iter()
+        .filter(|field| matches!(field.kind, FieldKind::Skip))
+        .map(|field| field.name);
+    // nest pairs, as tuple strategies are limited in size
+    let (strategy, pattern) = encoded.iter().rev().fold(
+        (quote!(proptest::strategy::Just(())), quote!(())),
+        |(strategy, pattern), (name, encoding)| {
+            let field_strategy = generate_strategy(encoding, recursion);
+            (
+                quote!((#field_strategy, #strategy)),
+                quote!((#name, #pattern)),
+            )
+        },
+    );
+    let field_name = encoded.iter().map(|(name, _)| name);
+    match encoding
+        .fields
+        .iter()
+        .find(|field| matches!(field.kind, FieldKind::Hash))
+    {
+        // the hash is that of the other fields' encoding, as computed when decoding
+        Some(hash) => {
+            let hash_name = hash.name;
+            quote_spanned! {
+                name.span()=> (#strategy).prop_map(|#pattern| {
+                    let mut value = #name {
+                        #(#field_name,)*
+                        #hash_name: Vec::new().into(),
+                        #(#skipped: Default::default(),)*
+                    };
+                    let mut encoded = Vec::new();
+                    let _ = tezos_data_encoding::enc::BinWriter::bin_write(&value, &mut encoded);
+                    value.#hash_name = tezos_data_encoding::nom::hashed(nom::combinator::rest)(&encoded)
+                        .map(|(_, (_, hash))| hash)
+                        .unwrap_or_default()
+                        .into();
+                    value
+                })
+            }
+        }
+        None => quote_spanned! {
+            name.span()=> (#strategy).prop_map(|#pattern| #name {
+                #(#field_name,)*
+                #(#skipped: Default::default(),)*
+            })
+        },
+    }
+}
+
+fn generate_enum_strategy(encoding: &EnumEncoding, recursion: Recursion) -> TokenStream {
+    let name = encoding.name;
+    // leaves only use the variants without recursive references
+    let tags = encoding
+        .tags
+        .iter()
+        .filter(|tag| !recursion.is_leaf_of(&tag.encoding))
+        .collect::<Vec<_>>();
+    if tags.is_empty() {
+        return quote_spanned! {
+            name.span()=> compile_error!("`roundtrip_test` needs an enum variant without recursive references")
+        };
+    }
+    let tag_strategy = tags.into_iter().map(|tag| {
+        let tag_name = tag.name;
+        match &tag.encoding {
+            Encoding::Unit => {
+                quote_spanned!(tag_name.span()=> proptest::strategy::LazyJust::new(|| #name::#tag_name).boxed())
+            }
+            encoding => {
+                let strategy = generate_strategy(encoding, recursion);
+                quote_spanned!(tag_name.span()=> (#strategy).prop_map(#name::#tag_name).boxed())
+            }
+        }
+    });
+    quote_spanned!(name.span()=> proptest::strategy::Union::new(vec![#(#tag_strategy),*]))
+}
@@ -49,6 +49,8 @@ fn generate_bin_write(encoding: &Encoding) -> TokenStream {
         Encoding::Dynamic(size, encoding, span) => {
             generate_dynamic_bin_write(size, encoding, *span)
         }
+        // nesting depth is only limited when decoding
+        Encoding::BoundedDepth(_, encoding, _) => generate_bin_write(encoding),
         Encoding::Zarith(span) => quote_spanned!(*span=> tezos_data_encoding::enc::zarith),
         Encoding::MuTez(span) => quote_spanned!(*span=> tezos_data_encoding::enc::mutez),
     }
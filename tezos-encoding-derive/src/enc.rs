@@ -43,6 +43,7 @@ pub(crate) fn generate_encoding(encoding: &Encoding) -> TokenStream {
         Encoding::Bounded(size, encoding, span) => generate_bounded_encoding(size, encoding, *span),
         Encoding::ShortDynamic(encoding, span) => generate_short_dynamic_encoding(encoding, *span),
         Encoding::Dynamic(size, encoding, span) => generate_dynamic_encoding(size, encoding, *span),
+        Encoding::BoundedDepth(_, encoding, _) => generate_encoding(encoding),
         Encoding::Zarith(span) => {
             quote_spanned!(*span=> tezos_data_encoding::encoding::Encoding::Z)
         }
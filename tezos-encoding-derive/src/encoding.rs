@@ -10,6 +10,7 @@ use proc_macro2::Span;
 pub struct DataWithEncoding<'a> {
     pub name: &'a syn::Ident,
     pub encoding: Encoding<'a>,
+    pub roundtrip_test: bool,
 }
 
 #[derive(Debug)]
@@ -82,6 +83,7 @@ pub enum Encoding<'a> {
     Bounded(syn::Expr, Box<Encoding<'a>>, Span),
     ShortDynamic(Box<Encoding<'a>>, Span),
     Dynamic(Option<syn::Expr>, Box<Encoding<'a>>, Span),
+    BoundedDepth(syn::Expr, Box<Encoding<'a>>, Span),
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Display, FromStr)]
@@ -8,12 +8,19 @@
 //!
 //! Rather than manually implementing the `NomReader` and `BinWriter` traits, you can
 //! annotate your structures directly.
+//!
+//! Annotating a non-generic type deriving `NomReader` with `#[encoding(roundtrip_test)]` also
+//! generates, for tests only, a `proptest` `Arbitrary` implementation and a property test checking
+//! that encoded values decode back to themselves. The crate using it needs `proptest` as a
+//! dev-dependency, and types of nested fields need `Arbitrary` implementations too. Those of hash
+//! types, `Zarith` and `Mutez` are enabled by the `proptest` feature of `tezos_data_encoding`.
 
 extern crate proc_macro;
 
 use proc_macro::TokenStream;
 use syn::{parse_macro_input, DeriveInput};
 
+mod arbitrary;
 mod bin;
 mod enc;
 mod encoding;
@@ -39,7 +46,12 @@ pub fn derive_nom_reader(input: TokenStream) -> TokenStream {
         Ok(encoding) => encoding,
         Err(e) => return e.into_compile_error().into(),
     };
-    let tokens = crate::nom::generate_nom_read_for_data(&input.generics, &encoding);
+    let mut tokens = crate::nom::generate_nom_read_for_data(&input.generics, &encoding);
+    if encoding.roundtrip_test {
+        tokens.extend(crate::arbitrary::generate_roundtrip_test_for_data(
+            &encoding,
+        ));
+    }
     tokens.into()
 }
 
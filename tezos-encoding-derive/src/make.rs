@@ -11,7 +11,15 @@ type Result<T> = std::result::Result<T, syn::Error>;
 
 pub fn make_encoding(input: &syn::DeriveInput) -> Result<DataWithEncoding> {
     let meta = &mut get_encoding_meta(&input.attrs)?;
-    let data_with_encoding = make_data_with_encoding(&input.data, &input.ident, meta)?;
+    let roundtrip_test = take_attribute_no_param(meta, &symbol::ROUNDTRIP_TEST)?;
+    if let (Some(roundtrip_test), false) = (&roundtrip_test, input.generics.params.is_empty()) {
+        return Err(error(
+            roundtrip_test.span,
+            "`roundtrip_test` is not supported for generic types",
+        ));
+    }
+    let mut data_with_encoding = make_data_with_encoding(&input.data, &input.ident, meta)?;
+    data_with_encoding.roundtrip_test = roundtrip_test.is_some();
     Ok(data_with_encoding)
 }
 
@@ -34,7 +42,11 @@ fn make_data_with_encoding<'a>(
     };
     let encoding = make_bounded_encoding(meta, encoding)?;
     assert_empty_meta(meta)?;
-    Ok(DataWithEncoding { name, encoding })
+    Ok(DataWithEncoding {
+        name,
+        encoding,
+        roundtrip_test: false,
+    })
 }
 
 fn make_struct_encoding<'a>(
@@ -355,6 +367,10 @@ fn make_bounded_encoding<'a>(
             Encoding::Dynamic(dynamic.param, Box::new(encoding), dynamic.span)
         } else if let Some(short_dynamic) = get_attribute(meta, &symbol::SHORT_DYNAMIC) {
             Encoding::ShortDynamic(Box::new(encoding), short_dynamic.span())
+        } else if let Some(max_depth) =
+            get_attribute_with_param(meta, &symbol::MAX_DEPTH, Some(&symbol::MAX), true)?
+        {
+            Encoding::BoundedDepth(max_depth.param, Box::new(encoding), max_depth.span)
         } else {
             return Ok(encoding);
         };
@@ -431,6 +447,20 @@ fn get_attribute_no_param(
         .transpose()
 }
 
+/// Removes attribute named `name` wherever it is, checking that it does not have any parameters.
+fn take_attribute_no_param(
+    meta: &mut Vec<syn::Meta>,
+    name: &symbol::Symbol,
+) -> Result<Option<AttrWithParam<()>>> {
+    match meta.iter().position(|meta| meta.path() == *name) {
+        Some(pos) => {
+            let mut attr = vec![meta.remove(pos)];
+            get_attribute_no_param(&mut attr, name)
+        }
+        None => Ok(None),
+    }
+}
+
 fn make_enum_encoding<'a>(
     data: &'a syn::DataEnum,
     name: &'a syn::Ident,
@@ -24,8 +24,9 @@ pub fn generate_nom_read_for_data(
         #[allow(unused_parens)]
         #[allow(clippy::unnecessary_cast)]
         #[allow(clippy::redundant_closure_call)]
+        #[allow(clippy::useless_conversion)]
         impl #impl_generics tezos_data_encoding::nom::NomReader for #name #ty_generics #where_clause {
-            fn nom_read(bytes: &[u8]) -> tezos_data_encoding::nom::NomResult<Self> {
+            fn nom_read(bytes: &[u8]) -> tezos_data_encoding::nom::NomResult<'_, Self> {
                 #nom_read(bytes)
             }
         }
@@ -49,6 +50,9 @@ fn generate_nom_read(encoding: &Encoding) -> TokenStream {
         Encoding::Bounded(size, encoding, span) => generate_bounded_nom_read(size, encoding, *span),
         Encoding::ShortDynamic(encoding, span) => generate_short_dynamic_nom_read(encoding, *span),
         Encoding::Dynamic(size, encoding, span) => generate_dynamic_nom_read(size, encoding, *span),
+        Encoding::BoundedDepth(max, encoding, span) => {
+            generate_bounded_depth_nom_read(max, encoding, *span)
+        }
         Encoding::Zarith(span) => quote_spanned!(*span=> tezos_data_encoding::nom::zarith),
         Encoding::MuTez(span) => quote_spanned!(*span=> tezos_data_encoding::nom::mutez),
     }
@@ -308,6 +312,23 @@ fn generate_bounded_nom_read(size: &syn::Expr, encoding: &Encoding, span: Span)
     quote_spanned!(span=> tezos_data_encoding::nom::bounded(#size, #nom_read))
 }
 
+fn generate_bounded_depth_nom_read(
+    max: &syn::Expr,
+    encoding: &Encoding,
+    span: Span,
+) -> TokenStream {
+    let nom_read = generate_nom_read(encoding);
+    quote_spanned! {
+        // one depth counter per bounded parser, so that limits do not interfere
+        span=> ({
+            ::std::thread_local! {
+                static DEPTH: ::std::cell::Cell<usize> = const { ::std::cell::Cell::new(0) };
+            }
+            tezos_data_encoding::nom::bounded_depth(&DEPTH, #max, #nom_read)
+        })
+    }
+}
+
 fn generate_short_dynamic_nom_read(encoding: &Encoding, span: Span) -> TokenStream {
     let nom_read = generate_nom_read(encoding);
     quote_spanned!(span=> tezos_data_encoding::nom::short_dynamic(#nom_read))
@@ -62,6 +62,9 @@ pub const BOUNDED: Symbol = Symbol("bounded");
 pub const DYNAMIC: Symbol = Symbol("dynamic");
 pub const SHORT_DYNAMIC: Symbol = Symbol("short_dynamic");
 
+/// Attribute used to limit nesting depth of recursive encodings.
+pub const MAX_DEPTH: Symbol = Symbol("max_depth");
+
 pub const TAGS: Symbol = Symbol("tags");
 pub const IGNORE_UNKNOWN: Symbol = Symbol("ignore_unknown");
 pub const TAG: Symbol = Symbol("tag");
@@ -71,6 +74,9 @@ pub const MU_TEZ: Symbol = Symbol("mutez");
 
 pub const RESERVE: Symbol = Symbol("reserve");
 
+/// Attribute used to generate round-trip property tests.
+pub const ROUNDTRIP_TEST: Symbol = Symbol("roundtrip_test");
+
 impl PartialEq<Symbol> for Ident {
     fn eq(&self, word: &Symbol) -> bool {
         self == word.0
@@ -355,6 +355,7 @@ encode_hash!(crypto::hash::Signature);
 encode_hash!(crypto::hash::BlsSignature);
 encode_hash!(crypto::hash::NonceHash);
 encode_hash!(crypto::hash::SmartRollupHash);
+encode_hash!(crypto::hash::ScriptExprHash);
 
 impl BinWriter for Mutez {
     fn bin_write(&self, out: &mut Vec<u8>) -> BinResult {
@@ -520,28 +521,27 @@ pub fn optional_field<'a, T: 'a>(
 }
 
 pub fn n_bignum(n: &BigUint, out: &mut Vec<u8>) -> BinResult {
-    let bytes = n.to_bytes_be();
-    let mut d = 0;
-    let mut acc = 0;
-    for c in 0..bytes.len() {
-        let i = bytes.len() - c - 1;
-        let mut byte = acc | (bytes[i] << d) & 0x7f;
-        if d == 7 {
-            acc = 0;
-            d = 0;
-        } else {
-            let acc_d = 7 - d;
-            acc = bytes[i] >> acc_d;
-            d = 8 - acc_d;
-        }
-        if !(i == 0 && acc == 0) {
-            byte |= 0x80;
+    // groups of 7 bits, least significant first, with the high bit set on all but the last
+    let start = out.len();
+    let mut acc = 0u16;
+    let mut bits = 0;
+    for byte in n.to_bytes_le() {
+        acc |= u16::from(byte) << bits;
+        bits += 8;
+        while bits >= 7 {
+            out.push(acc as u8 | 0x80);
+            acc >>= 7;
+            bits -= 7;
         }
-        out.push(byte);
     }
     if acc != 0 {
-        out.push(acc);
+        out.push(acc as u8 | 0x80);
+    }
+    while out.len() > start + 1 && out[out.len() - 1] == 0x80 {
+        out.pop();
     }
+    let last = out.len() - 1;
+    out[last] &= 0x7f;
     Ok(())
 }
 
@@ -597,6 +597,8 @@ mod test {
             ("ffff", "ffff03"),
             ("10000", "808004"),
             ("10001", "818004"),
+            ("100000000000000", "808080808080808001"),
+            ("7fffffffffffffff", "ffffffffffffffff7f"),
         ];
 
         use super::{BinWriter, Mutez};
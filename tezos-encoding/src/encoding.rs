@@ -303,6 +303,13 @@ pub trait HasEncoding {
     fn encoding() -> Encoding;
 }
 
+impl<T: HasEncoding> HasEncoding for Box<T> {
+    fn encoding() -> Encoding {
+        // Boxes are used for recursive types, which `Encoding` cannot describe.
+        Encoding::Custom
+    }
+}
+
 macro_rules! hash_has_encoding {
     ($hash_name:ident, $enc_ref_name:ident) => {
         impl HasEncoding for crypto::hash::$hash_name {
@@ -342,6 +349,7 @@ hash_has_encoding!(Signature, SIGNATURE);
 hash_has_encoding!(BlsSignature, BLS_SIGNATURE_HASH);
 hash_has_encoding!(NonceHash, NONCE_HASH);
 hash_has_encoding!(SmartRollupHash, SMART_ROLLUP_HASH);
+hash_has_encoding!(ScriptExprHash, SCRIPT_EXPR_HASH);
 
 /// Creates impl HasEncoding for given struct backed by lazy_static ref instance with encoding.
 #[macro_export]
@@ -51,6 +51,10 @@
 
 extern crate tezos_crypto_rs as crypto;
 
+// Lets derived implementations refer to this crate in its own tests.
+#[cfg(test)]
+extern crate self as tezos_data_encoding;
+
 mod bit_utils;
 pub mod types;
 
@@ -63,3 +67,122 @@ pub mod nom;
 
 #[cfg(feature = "fuzzing")]
 pub mod fuzzing;
+
+#[cfg(test)]
+mod test {
+    use crate::enc::BinWriter;
+    use crate::encoding::{Encoding, HasEncoding};
+    use crate::nom::NomReader;
+
+    #[derive(Debug, Clone, PartialEq, HasEncoding, NomReader, BinWriter)]
+    #[encoding(roundtrip_test)]
+    struct Inner {
+        #[encoding(sized = "4", bytes)]
+        fixed: Vec<u8>,
+        #[encoding(string = "3")]
+        name: String,
+        flag: bool,
+    }
+
+    #[derive(Debug, Clone, PartialEq, HasEncoding, NomReader, BinWriter)]
+    #[encoding(roundtrip_test)]
+    struct Number {
+        value: u16,
+    }
+
+    #[derive(Debug, Clone, PartialEq, HasEncoding, NomReader, BinWriter)]
+    #[encoding(roundtrip_test)]
+    enum Kind {
+        Empty,
+        Inner(Inner),
+        #[encoding(tag = 0x10)]
+        Number(Number),
+    }
+
+    #[derive(Debug, Clone, PartialEq, HasEncoding, NomReader, BinWriter)]
+    #[encoding(roundtrip_test)]
+    struct Outer {
+        #[encoding(dynamic, list)]
+        kinds: Vec<Kind>,
+        #[encoding(dynamic, list = "4")]
+        bounded: Vec<u8>,
+        #[encoding(bounded = "4", dynamic, list)]
+        items: Vec<u32>,
+        #[encoding(dynamic = "6", list)]
+        words: Vec<u16>,
+        maybe: Option<Inner>,
+        small: i8,
+        int32: i32,
+        #[encoding(skip)]
+        skipped: u64,
+    }
+
+    #[derive(Debug, Clone, PartialEq, HasEncoding, NomReader, BinWriter)]
+    #[encoding(roundtrip_test)]
+    struct Hashed {
+        block: crypto::hash::BlockHash,
+        amount: crate::types::Mutez,
+        value: crate::types::Zarith,
+        #[encoding(hash)]
+        hash: Vec<u8>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, HasEncoding, NomReader, BinWriter)]
+    #[encoding(max_depth = "2", roundtrip_test)]
+    struct Leaf {
+        inner: Option<Box<Leaf>>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, HasEncoding, NomReader, BinWriter)]
+    #[encoding(max_depth = "100", roundtrip_test)]
+    struct Tree {
+        child: Option<Box<Tree>>,
+        leaf: Leaf,
+    }
+
+    #[derive(Debug, Clone, PartialEq, HasEncoding, NomReader, BinWriter)]
+    #[encoding(max_depth = "4", roundtrip_test)]
+    enum Expr {
+        Int(Number),
+        Neg(Box<Expr>),
+        Pair(Pair),
+    }
+
+    #[derive(Debug, Clone, PartialEq, HasEncoding, NomReader, BinWriter)]
+    #[encoding(roundtrip_test)]
+    struct Pair {
+        #[encoding(dynamic, list)]
+        items: Vec<Number>,
+    }
+
+    #[test]
+    fn max_depth_of_nested_types() {
+        // a tree 3 levels deep, with leaves 2 levels deep
+        let leaf = [0xff, 0x00];
+        let input = [&[0xff, 0xff, 0x00][..], &leaf, &leaf, &leaf].concat();
+        let (rest, tree) = Tree::nom_read(&input).unwrap();
+        assert!(rest.is_empty());
+        let mut output = Vec::new();
+        tree.bin_write(&mut output).unwrap();
+        assert_eq!(output, input);
+
+        let deep_leaf = [0xff, 0xff, 0x00];
+        let input = [&[0x00][..], &deep_leaf].concat();
+        assert!(matches!(
+            Tree::nom_read(&input),
+            Err(nom::Err::Failure(e)) if e.is_depth_limit()
+        ));
+    }
+
+    #[test]
+    fn encoding_of_recursive_types() {
+        let fields = match Tree::encoding() {
+            Encoding::Obj("Tree", fields) => fields,
+            encoding => panic!("unexpected encoding {:?}", encoding),
+        };
+        assert!(matches!(
+            fields[0].get_encoding(),
+            Encoding::OptionalField(encoding) if matches!(**encoding, Encoding::Custom)
+        ));
+    }
+}
@@ -67,6 +67,8 @@ pub mod error {
         InvalidTag(String),
         /// Other errors can be generated by custom parsers.
         Hash(Blake2bError),
+        /// Maximum nesting depth exceeded.
+        DepthLimit(usize),
     }
 
     /// Specific bounded encoding kind.
@@ -133,6 +135,21 @@ pub mod error {
                 other: None,
             }
         }
+
+        pub fn depth_limit(input: NomInput<'a>, max: usize) -> Self {
+            Self {
+                input,
+                kind: DecodeErrorKind::DepthLimit(max),
+                other: None,
+            }
+        }
+
+        pub fn is_depth_limit(&self) -> bool {
+            match self.kind {
+                DecodeErrorKind::DepthLimit(_) => true,
+                _ => self.other.as_ref().map_or(false, |e| e.is_depth_limit()),
+            }
+        }
     }
 
     impl<I> nom::error::ParseError<I> for DecodeError<I> {
@@ -206,6 +223,9 @@ pub mod error {
             DecodeErrorKind::UnknownTag(tag) => write!(res, " caused by unsupported tag `{}`", tag),
             DecodeErrorKind::InvalidTag(tag) => write!(res, " caused by invalid tag `{}`", tag),
             DecodeErrorKind::Hash(e) => write!(res, " because of error calculating hash: {}", e),
+            DecodeErrorKind::DepthLimit(max) => {
+                write!(res, " caused by exceeding maximum nesting depth `{}`", max)
+            }
         };
 
         if let Some(other) = error.other {
@@ -269,6 +289,13 @@ hash_nom_reader!(Signature);
 hash_nom_reader!(BlsSignature);
 hash_nom_reader!(NonceHash);
 hash_nom_reader!(SmartRollupHash);
+hash_nom_reader!(ScriptExprHash);
+
+impl<T: NomReader> NomReader for Box<T> {
+    fn nom_read(bytes: &[u8]) -> NomResult<'_, Self> {
+        map(T::nom_read, Box::new)(bytes)
+    }
+}
 
 impl NomReader for Zarith {
     fn nom_read(bytes: &[u8]) -> NomResult<Self> {
@@ -473,6 +500,69 @@ where
     }
 }
 
+/// Nesting depth of a [bounded_depth] parser on the current thread.
+///
+/// Declared with `thread_local!`, one per bounded parser:
+///
+/// ```rust
+/// std::thread_local! {
+///     static DEPTH: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+/// }
+/// ```
+pub type Depth = std::thread::LocalKey<std::cell::Cell<usize>>;
+
+/// Restores the nesting depth when a [bounded_depth] parser returns or unwinds.
+struct DepthGuard(&'static Depth, usize);
+
+impl Drop for DepthGuard {
+    fn drop(&mut self) {
+        self.0.with(|depth| depth.set(self.1));
+    }
+}
+
+/// Applies the parser `f` to the input, failing if it is nested more than `max` times
+/// within itself, e.g. when decoding recursive structures.
+///
+/// Nesting is counted with `depth`, which must be used by this parser only, so that
+/// the limit holds wherever the parser is used, whatever other bounded parsers it is
+/// nested in. Exceeding it is reported as a [nom::Err::Failure], as backtracking
+/// cannot make the input any shallower.
+///
+/// ```rust
+/// use tezos_data_encoding::nom::NomReader;
+///
+/// #[derive(Debug, Clone, PartialEq, NomReader)]
+/// #[encoding(max_depth = "2")]
+/// struct Nested {
+///     inner: Option<Box<Nested>>,
+/// }
+///
+/// assert!(Nested::nom_read(&[0xff, 0x00]).is_ok());
+/// assert!(matches!(
+///     Nested::nom_read(&[0xff, 0xff, 0x00]),
+///     Err(nom::Err::Failure(e)) if e.is_depth_limit()
+/// ));
+/// ```
+#[inline(always)]
+pub fn bounded_depth<'a, O, F>(
+    depth: &'static Depth,
+    max: usize,
+    mut f: F,
+) -> impl FnMut(NomInput<'a>) -> NomResult<'a, O>
+where
+    F: FnMut(NomInput<'a>) -> NomResult<'a, O>,
+{
+    move |input| {
+        let current = depth.with(|depth| depth.get());
+        if current >= max {
+            return Err(Err::Failure(DecodeError::depth_limit(input, max)));
+        }
+        let _guard = DepthGuard(depth, current);
+        depth.with(|depth| depth.set(current + 1));
+        f(input)
+    }
+}
+
 /// Reserves `size` trailing bytes of the input and applies parser to the rest of the input.
 #[inline(always)]
 pub fn reserve<'a, O, F>(size: usize, mut parser: F) -> impl FnMut(NomInput<'a>) -> NomResult<'a, O>
@@ -833,6 +923,65 @@ mod test {
         }
     }
 
+    thread_local! {
+        static NESTED_DEPTH: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+        static OUTER_DEPTH: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+    }
+
+    fn nested(input: NomInput<'_>) -> NomResult<'_, u32> {
+        bounded_depth(&NESTED_DEPTH, 3, |input| {
+            let (input, more) = boolean(input)?;
+            if more {
+                map(nested, |n| n + 1)(input)
+            } else {
+                Ok((input, 0))
+            }
+        })(input)
+    }
+
+    #[test]
+    fn test_bounded_depth() {
+        assert_eq!(nested(&[0xff, 0xff, 0x00]), Ok((&[][..], 2)));
+
+        let input = &[0xff, 0xff, 0xff, 0x00];
+        let res = nested(input);
+        assert_eq!(
+            res,
+            Err(Err::Failure(DecodeError {
+                input: &input[3..],
+                kind: DecodeErrorKind::DepthLimit(3),
+                other: None,
+            }))
+        );
+
+        // depth is restored once the failing parser returns
+        assert_eq!(nested(&[0x00]), Ok((&[][..], 0)));
+    }
+
+    fn outer(input: NomInput<'_>) -> NomResult<'_, u32> {
+        bounded_depth(&OUTER_DEPTH, 100, |input| {
+            let (input, more) = boolean(input)?;
+            if more {
+                map(outer, |n| n + 1)(input)
+            } else {
+                nested(input)
+            }
+        })(input)
+    }
+
+    #[test]
+    fn test_bounded_depth_nested_limits() {
+        // limits of other bounded parsers do not count against the inner one
+        assert_eq!(
+            outer(&[0xff, 0xff, 0xff, 0x00, 0xff, 0xff, 0x00]),
+            Ok((&[][..], 5))
+        );
+        assert!(matches!(
+            outer(&[0xff, 0xff, 0xff, 0x00, 0xff, 0xff, 0xff, 0x00]),
+            Err(Err::Failure(e)) if e.kind == DecodeErrorKind::DepthLimit(3)
+        ));
+    }
+
     fn hex_to_bigint(s: &str) -> BigInt {
         num_bigint::BigInt::from_i64(i64::from_str_radix(s, 16).unwrap()).unwrap()
     }
@@ -88,9 +88,29 @@ impl From<&Zarith> for BigInt {
 
 has_encoding!(Zarith, ZARITH_ENCODING, { Encoding::Z });
 
+#[cfg(any(test, feature = "proptest"))]
+impl proptest::arbitrary::Arbitrary for Zarith {
+    type Parameters = ();
+    type Strategy = proptest::strategy::BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        use proptest::strategy::Strategy;
+        // magnitudes up to 128 bits, wider than machine integers
+        (
+            proptest::arbitrary::any::<bool>(),
+            proptest::collection::vec(proptest::arbitrary::any::<u8>(), 0..=16),
+        )
+            .prop_map(|(negative, magnitude)| {
+                let sign = if negative { Sign::Minus } else { Sign::Plus };
+                Zarith(num_bigint::BigInt::from_bytes_le(sign, &magnitude))
+            })
+            .boxed()
+    }
+}
+
 /// Mutez number
 #[cfg_attr(feature = "fuzzing", derive(fuzzcheck::DefaultMutator))]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Mutez(
     #[cfg_attr(feature = "fuzzing", field_mutator(BigIntMutator))] pub num_bigint::BigInt,
 );
@@ -167,6 +187,18 @@ impl From<&Mutez> for BigInt {
 
 has_encoding!(Mutez, MUTEZ_ENCODING, { Encoding::Mutez });
 
+/// Amounts representable as `int64`, as in the protocol.
+#[cfg(any(test, feature = "proptest"))]
+impl proptest::arbitrary::Arbitrary for Mutez {
+    type Parameters = ();
+    type Strategy = proptest::strategy::BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        use proptest::strategy::Strategy;
+        (0..=i64::MAX).prop_map(|n| Mutez(n.into())).boxed()
+    }
+}
+
 #[derive(Clone, PartialEq, Eq)]
 //#[cfg_attr(feature = "fuzzing", derive(fuzzcheck::DefaultMutator))]
 pub struct SizedBytes<const SIZE: usize>(pub [u8; SIZE]);
@@ -0,0 +1,263 @@
+// SPDX-FileCopyrightText: 2023 TriliTech <contact@trili.tech>
+// SPDX-License-Identifier: MIT
+
+//! Static size and gas estimation of expressions, so that filters can reject oversized
+//! payloads before they are validated by the protocol.
+//!
+//! ```rust
+//! use tezos_micheline::cost::{Limits, Stats};
+//! use tezos_micheline::{Node, Primitive};
+//!
+//! let node = Node::prim(Primitive::D_Pair, vec![Node::int(1), Node::string("foo")]);
+//! let stats = Stats::of(&node);
+//! assert_eq!(stats, Stats { size: 12, nodes: 3, depth: 2 });
+//!
+//! let limits = Limits { max_size: 8, max_nodes: 100, max_depth: 10 };
+//! assert!(limits.check(&node).is_err());
+//! ```
+
+use thiserror::Error;
+
+use crate::node::Node;
+
+/// Size of the tag of a node, of the primitive of an application, and of the length
+/// prefix of strings and sequences.
+const TAG_SIZE: usize = 1;
+const PRIM_SIZE: usize = 1;
+const LENGTH_SIZE: usize = 4;
+
+/// Statistics of an expression, computed without encoding it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Size of the binary encoding, in bytes.
+    pub size: usize,
+    /// Number of nodes, annotations excluded.
+    pub nodes: usize,
+    /// Nesting depth, literals being at depth 1.
+    pub depth: usize,
+}
+
+impl Stats {
+    /// Statistics of the expression.
+    pub fn of(node: &Node) -> Self {
+        match node {
+            Node::Int(i) => Stats {
+                // sign and 6 bits in the first byte, 7 bits in each of the next ones
+                size: TAG_SIZE + 1 + (i.bits().saturating_sub(6) as usize + 6) / 7,
+                nodes: 1,
+                depth: 1,
+            },
+            Node::String(s) => Stats::leaf(LENGTH_SIZE + s.len()),
+            Node::Bytes(b) => Stats::leaf(LENGTH_SIZE + b.len()),
+            Node::Seq(nodes) => Stats::parent(TAG_SIZE + LENGTH_SIZE, nodes),
+            Node::Prim { args, annots, .. } => {
+                let args_size = if args.len() > 2 { LENGTH_SIZE } else { 0 };
+                let annots_size = if args.len() > 2 || !annots.is_empty() {
+                    // annotations are joined with spaces
+                    let len = annots.iter().map(String::len).sum::<usize>();
+                    LENGTH_SIZE + len + annots.len().saturating_sub(1)
+                } else {
+                    0
+                };
+                Stats::parent(TAG_SIZE + PRIM_SIZE + args_size + annots_size, args)
+            }
+        }
+    }
+
+    fn leaf(size: usize) -> Self {
+        Stats {
+            size: TAG_SIZE + size,
+            nodes: 1,
+            depth: 1,
+        }
+    }
+
+    fn parent(size: usize, children: &[Node]) -> Self {
+        children.iter().map(Stats::of).fold(
+            Stats {
+                size,
+                nodes: 1,
+                depth: 1,
+            },
+            |stats, child| Stats {
+                size: stats.size + child.size,
+                nodes: stats.nodes + child.nodes,
+                depth: stats.depth.max(child.depth + 1),
+            },
+        )
+    }
+}
+
+/// Error returned when an expression exceeds [Limits].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum LimitError {
+    #[error("expression of {0} bytes exceeds size limit")]
+    Size(usize),
+    #[error("expression of {0} nodes exceeds node limit")]
+    Nodes(usize),
+    #[error("expression nested {0} levels deep exceeds depth limit")]
+    Depth(usize),
+}
+
+/// Limits on the size of accepted expressions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub max_size: usize,
+    pub max_nodes: usize,
+    pub max_depth: usize,
+}
+
+impl Limits {
+    /// Checks the expression against the limits, returning its statistics if it is
+    /// within them.
+    pub fn check(&self, node: &Node) -> Result<Stats, LimitError> {
+        let stats = Stats::of(node);
+        if stats.size > self.max_size {
+            Err(LimitError::Size(stats.size))
+        } else if stats.nodes > self.max_nodes {
+            Err(LimitError::Nodes(stats.nodes))
+        } else if stats.depth > self.max_depth {
+            Err(LimitError::Depth(stats.depth))
+        } else {
+            Ok(stats)
+        }
+    }
+}
+
+/// Linear model of the gas needed to deserialize an expression.
+///
+/// The coefficients are protocol constants, in milligas, and are to be taken from the
+/// protocol the estimation is made for, e.g. with [CostModel::oxford].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CostModel {
+    pub base: u64,
+    pub per_byte: u64,
+    pub per_node: u64,
+}
+
+impl CostModel {
+    /// Constants of the Oxford protocol (`proto_018_Proxford`), from `script_repr.ml`:
+    /// 20 milligas per byte for decoding an expression
+    /// (`deserialization_cost_estimated_from_bytes`), and 51 milligas per node for
+    /// stripping its locations (`strip_locations_cost`).
+    pub const fn oxford() -> Self {
+        CostModel {
+            base: 0,
+            per_byte: 20,
+            per_node: 51,
+        }
+    }
+
+    /// Worst-case deserialization cost of an expression with the given statistics, in
+    /// milligas.
+    pub fn deserialization_milligas(&self, stats: &Stats) -> u64 {
+        self.base
+            .saturating_add(self.per_byte.saturating_mul(stats.size as u64))
+            .saturating_add(self.per_node.saturating_mul(stats.nodes as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitive::Primitive;
+    use num_bigint::BigInt;
+    use tezos_data_encoding::enc::BinWriter;
+
+    fn assert_size(node: Node) {
+        let mut bytes = Vec::new();
+        node.bin_write(&mut bytes).unwrap();
+        assert_eq!(Stats::of(&node).size, bytes.len(), "{}", node);
+    }
+
+    #[test]
+    fn size_matches_encoding() {
+        for i in [0i64, 1, -1, 63, 64, -64, 8191, 8192, i64::MAX, i64::MIN] {
+            assert_size(Node::int(i));
+        }
+        assert_size(Node::Int(BigInt::from(1) << 1000));
+        assert_size(Node::string(""));
+        assert_size(Node::string("foo"));
+        assert_size(Node::bytes(vec![0; 10]));
+        assert_size(Node::seq(vec![]));
+        assert_size(Node::seq(vec![Node::int(1), Node::seq(vec![])]));
+        let annots = vec!["%a".to_string(), ":bc".to_string()];
+        for n in 0..4 {
+            let args = vec![Node::int(1); n];
+            assert_size(Node::prim(Primitive::D_Pair, args.clone()));
+            assert_size(Node::prim_annot(Primitive::T_pair, args, annots.clone()));
+        }
+    }
+
+    #[test]
+    fn nodes_and_depth() {
+        let node = Node::seq(vec![
+            Node::prim_annot(
+                Primitive::D_Some,
+                vec![Node::int(1)],
+                vec!["%a".to_string()],
+            ),
+            Node::string("b"),
+        ]);
+        let stats = Stats::of(&node);
+        assert_eq!((stats.nodes, stats.depth), (4, 3));
+    }
+
+    #[test]
+    fn limits() {
+        let node = Node::seq(vec![Node::seq(vec![Node::int(1), Node::int(2)])]);
+        let stats = Stats::of(&node);
+        let limits = Limits {
+            max_size: stats.size,
+            max_nodes: stats.nodes,
+            max_depth: stats.depth,
+        };
+        assert_eq!(limits.check(&node), Ok(stats));
+        for (limits, err) in [
+            (
+                Limits {
+                    max_size: stats.size - 1,
+                    ..limits
+                },
+                LimitError::Size(stats.size),
+            ),
+            (
+                Limits {
+                    max_nodes: stats.nodes - 1,
+                    ..limits
+                },
+                LimitError::Nodes(stats.nodes),
+            ),
+            (
+                Limits {
+                    max_depth: stats.depth - 1,
+                    ..limits
+                },
+                LimitError::Depth(stats.depth),
+            ),
+        ] {
+            assert_eq!(limits.check(&node), Err(err));
+        }
+    }
+
+    #[test]
+    fn deserialization_cost() {
+        let model = CostModel {
+            base: 100,
+            per_byte: 10,
+            per_node: 1,
+        };
+        let stats = Stats {
+            size: 20,
+            nodes: 3,
+            depth: 2,
+        };
+        assert_eq!(model.deserialization_milligas(&stats), 303);
+        let huge = CostModel {
+            per_byte: u64::MAX,
+            ..model
+        };
+        assert_eq!(huge.deserialization_milligas(&stats), u64::MAX);
+        assert_eq!(CostModel::oxford().deserialization_milligas(&stats), 553);
+    }
+}
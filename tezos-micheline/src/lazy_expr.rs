@@ -0,0 +1,134 @@
+// SPDX-FileCopyrightText: 2023 TriliTech <contact@trili.tech>
+// SPDX-License-Identifier: MIT
+
+//! Lazily decoded expressions, such as the scripts and parameters of operations that
+//! are only relayed.
+
+use once_cell::sync::OnceCell;
+use tezos_data_encoding::{
+    enc::{self, BinError, BinResult, BinWriter},
+    encoding::{Encoding, HasEncoding},
+    nom::{self as nom_enc, NomReader, NomResult},
+};
+
+use crate::node::Node;
+use crate::pack::{decode, UnpackError};
+
+/// Expression kept in its binary encoding, and decoded on first access.
+///
+/// Encoded as length-prefixed bytes, like `lazy_expr` in octez, and always written back
+/// as the bytes it was read from.
+#[derive(Debug, Clone)]
+pub struct LazyExpr {
+    bytes: Vec<u8>,
+    node: OnceCell<Result<Node, UnpackError>>,
+}
+
+impl LazyExpr {
+    /// Expression encoded as `bytes`, which are not decoded until needed.
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        LazyExpr {
+            bytes,
+            node: OnceCell::new(),
+        }
+    }
+
+    /// Already decoded expression.
+    pub fn from_node(node: Node) -> Result<Self, BinError> {
+        let mut bytes = Vec::new();
+        node.bin_write(&mut bytes)?;
+        Ok(LazyExpr {
+            bytes,
+            node: OnceCell::with_value(Ok(node)),
+        })
+    }
+
+    /// Binary encoding of the expression.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Binary encoding of the expression.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// The expression, decoded on the first call.
+    pub fn node(&self) -> Result<&Node, UnpackError> {
+        self.node
+            .get_or_init(|| decode(&self.bytes))
+            .as_ref()
+            .map_err(Clone::clone)
+    }
+
+    /// Whether the expression has been decoded already.
+    pub fn is_decoded(&self) -> bool {
+        self.node.get().is_some()
+    }
+}
+
+impl PartialEq for LazyExpr {
+    fn eq(&self, other: &Self) -> bool {
+        self.bytes == other.bytes
+    }
+}
+
+impl Eq for LazyExpr {}
+
+impl HasEncoding for LazyExpr {
+    fn encoding() -> Encoding {
+        Encoding::dynamic(Encoding::Bytes)
+    }
+}
+
+impl NomReader for LazyExpr {
+    fn nom_read(input: &[u8]) -> NomResult<'_, Self> {
+        nom::combinator::map(nom_enc::dynamic(nom_enc::bytes), LazyExpr::from_bytes)(input)
+    }
+}
+
+impl BinWriter for LazyExpr {
+    fn bin_write(&self, out: &mut Vec<u8>) -> BinResult {
+        enc::dynamic(enc::bytes)(&self.bytes, out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitive::Primitive;
+
+    #[test]
+    fn decoded_on_access() {
+        let node = Node::prim(Primitive::D_Pair, vec![Node::int(1), Node::string("a")]);
+        let expr = LazyExpr::from_node(node.clone()).unwrap();
+        assert!(expr.is_decoded());
+
+        let mut encoded = Vec::new();
+        expr.bin_write(&mut encoded).unwrap();
+        assert_eq!(&encoded[..4], &(expr.bytes().len() as u32).to_be_bytes());
+
+        let (rest, read) = LazyExpr::nom_read(&encoded).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(read, expr);
+        assert!(!read.is_decoded());
+        assert_eq!(read.node(), Ok(&node));
+        assert!(read.is_decoded());
+    }
+
+    #[test]
+    fn invalid_expression_is_relayed() {
+        // unknown node tag, and trailing bytes
+        for bytes in [vec![0xff], vec![0x00, 0x01, 0x00]] {
+            let mut encoded = Vec::new();
+            enc::dynamic(enc::bytes)(&bytes, &mut encoded).unwrap();
+
+            let (_, expr) = LazyExpr::nom_read(&encoded).unwrap();
+            assert!(expr.node().is_err());
+
+            let mut written = Vec::new();
+            expr.bin_write(&mut written).unwrap();
+            assert_eq!(written, encoded);
+        }
+    }
+}
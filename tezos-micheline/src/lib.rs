@@ -0,0 +1,28 @@
+// SPDX-FileCopyrightText: 2023 TriliTech <contact@trili.tech>
+// SPDX-License-Identifier: MIT
+#![forbid(unsafe_code)]
+
+//! Micheline expressions, the generic syntax of Michelson code and data.
+//!
+//! Provides the binary encoding of expressions, compatible with octez, along with
+//! `PACK`/`UNPACK` and `expr` script expression hashes, and a typed Michelson AST
+//! printed in Michelson concrete syntax.
+//!
+//! ```rust
+//! use tezos_micheline::{pack, Node, Primitive};
+//!
+//! let pair = Node::prim(Primitive::D_Pair, vec![Node::int(1), Node::int(2)]);
+//! assert_eq!(pack::pack(&pair).unwrap(), vec![0x05, 0x07, 0x07, 0x00, 0x01, 0x00, 0x02]);
+//! ```
+
+pub mod cost;
+pub mod lazy_expr;
+pub mod node;
+pub mod pack;
+pub mod primitive;
+pub mod typed;
+
+pub use node::Node;
+pub use primitive::Primitive;
+
+extern crate tezos_crypto_rs as crypto;
@@ -0,0 +1,502 @@
+// SPDX-FileCopyrightText: 2023 TriliTech <contact@trili.tech>
+// SPDX-License-Identifier: MIT
+
+//! Micheline expression tree and its binary encoding.
+
+use std::fmt;
+
+use nom::{combinator::map, number::complete::u8 as nom_u8, sequence::tuple, Err};
+use num_bigint::BigInt;
+use tezos_data_encoding::{
+    enc::{self, BinResult, BinWriter},
+    encoding::{Encoding, HasEncoding},
+    nom::{self as nom_enc, error::DecodeError, NomInput, NomReader, NomResult},
+    types::Zarith,
+};
+
+use crate::primitive::Primitive;
+
+const TAG_INT: u8 = 0;
+const TAG_STRING: u8 = 1;
+const TAG_SEQ: u8 = 2;
+const TAG_PRIM_0: u8 = 3;
+const TAG_PRIM_0_ANNOTS: u8 = 4;
+const TAG_PRIM_1: u8 = 5;
+const TAG_PRIM_1_ANNOTS: u8 = 6;
+const TAG_PRIM_2: u8 = 7;
+const TAG_PRIM_2_ANNOTS: u8 = 8;
+const TAG_PRIM_N: u8 = 9;
+const TAG_BYTES: u8 = 10;
+
+/// Default limit on the nesting depth of decoded expressions, keeping adversarial
+/// inputs from exhausting the stack.
+pub const MAX_DEPTH: usize = 256;
+
+/// Micheline expression, with location information stripped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
+    /// Arbitrary precision integer.
+    Int(BigInt),
+    /// String literal.
+    String(String),
+    /// Byte sequence literal.
+    Bytes(Vec<u8>),
+    /// Application of a primitive to arguments, with optional annotations.
+    Prim {
+        prim: Primitive,
+        args: Vec<Node>,
+        annots: Vec<String>,
+    },
+    /// Sequence of expressions, `{ ... }`.
+    Seq(Vec<Node>),
+}
+
+impl Node {
+    /// Integer node.
+    pub fn int(i: impl Into<BigInt>) -> Self {
+        Node::Int(i.into())
+    }
+
+    /// String node.
+    pub fn string(s: impl Into<String>) -> Self {
+        Node::String(s.into())
+    }
+
+    /// Bytes node.
+    pub fn bytes(b: impl Into<Vec<u8>>) -> Self {
+        Node::Bytes(b.into())
+    }
+
+    /// Primitive application without annotations.
+    pub fn prim(prim: Primitive, args: Vec<Node>) -> Self {
+        Node::Prim {
+            prim,
+            args,
+            annots: Vec::new(),
+        }
+    }
+
+    /// Primitive application with annotations, e.g. `%field` or `:type`.
+    pub fn prim_annot(prim: Primitive, args: Vec<Node>, annots: Vec<String>) -> Self {
+        Node::Prim { prim, args, annots }
+    }
+
+    /// Sequence node.
+    pub fn seq(nodes: Vec<Node>) -> Self {
+        Node::Seq(nodes)
+    }
+}
+
+impl fmt::Display for Node {
+    /// Formats the expression in Michelson concrete syntax, on a single line.
+    ///
+    /// With the alternate flag (`{:#}`), elements of sequences are on separate lines,
+    /// aligned after the opening brace.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let multi_line = f.alternate();
+        self.fmt_nested(
+            &mut Printer {
+                out: f,
+                column: 0,
+                multi_line,
+            },
+            false,
+        )
+    }
+}
+
+/// Writer keeping track of the current column, to indent sequences.
+struct Printer<'a, W> {
+    out: &'a mut W,
+    column: usize,
+    multi_line: bool,
+}
+
+impl<W: fmt::Write> fmt::Write for Printer<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.column = match s.rfind('\n') {
+            Some(i) => s[i + 1..].chars().count(),
+            None => self.column + s.chars().count(),
+        };
+        self.out.write_str(s)
+    }
+}
+
+impl Node {
+    fn fmt_nested<W: fmt::Write>(&self, f: &mut Printer<'_, W>, nested: bool) -> fmt::Result {
+        use fmt::Write;
+
+        match self {
+            Node::Int(i) => write!(f, "{}", i),
+            Node::String(s) => {
+                f.write_str("\"")?;
+                for c in s.chars() {
+                    match c {
+                        '"' => f.write_str("\\\"")?,
+                        '\\' => f.write_str("\\\\")?,
+                        '\n' => f.write_str("\\n")?,
+                        '\r' => f.write_str("\\r")?,
+                        '\t' => f.write_str("\\t")?,
+                        c => f.write_char(c)?,
+                    }
+                }
+                f.write_str("\"")
+            }
+            Node::Bytes(b) => {
+                f.write_str("0x")?;
+                b.iter().try_for_each(|b| write!(f, "{:02x}", b))
+            }
+            Node::Seq(nodes) if nodes.is_empty() => f.write_str("{}"),
+            Node::Seq(nodes) => {
+                f.write_str("{ ")?;
+                let indent = f.column;
+                for (i, node) in nodes.iter().enumerate() {
+                    if i > 0 && f.multi_line {
+                        write!(f, " ;\n{:indent$}", "", indent = indent)?;
+                    } else if i > 0 {
+                        f.write_str(" ; ")?;
+                    }
+                    node.fmt_nested(f, false)?;
+                }
+                f.write_str(" }")
+            }
+            Node::Prim { prim, args, annots } => {
+                let wrap = nested && !(args.is_empty() && annots.is_empty());
+                if wrap {
+                    f.write_str("(")?;
+                }
+                f.write_str(prim.name())?;
+                annots.iter().try_for_each(|a| write!(f, " {}", a))?;
+                for arg in args {
+                    f.write_str(" ")?;
+                    arg.fmt_nested(f, true)?;
+                }
+                if wrap {
+                    f.write_str(")")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl HasEncoding for Node {
+    fn encoding() -> Encoding {
+        // Micheline is recursive, which `Encoding` cannot describe.
+        Encoding::Custom
+    }
+}
+
+impl Node {
+    /// Decodes an expression nested at most `max_depth` levels deep.
+    ///
+    /// Each sequence and primitive application adds a level, as do literals.
+    pub fn nom_read_with_max_depth(max_depth: usize, input: &[u8]) -> NomResult<'_, Self> {
+        node(max_depth, input)
+    }
+}
+
+impl NomReader for Node {
+    /// Decodes an expression nested at most [MAX_DEPTH] levels deep.
+    fn nom_read(input: &[u8]) -> NomResult<'_, Self> {
+        node(MAX_DEPTH, input)
+    }
+}
+
+impl BinWriter for Node {
+    fn bin_write(&self, out: &mut Vec<u8>) -> BinResult {
+        match self {
+            Node::Int(i) => {
+                enc::put_byte(&TAG_INT, out);
+                Zarith(i.clone()).bin_write(out)
+            }
+            Node::String(s) => {
+                enc::put_byte(&TAG_STRING, out);
+                enc::string(s, out)
+            }
+            Node::Bytes(b) => {
+                enc::put_byte(&TAG_BYTES, out);
+                enc::dynamic(enc::bytes)(b, out)
+            }
+            Node::Seq(nodes) => {
+                enc::put_byte(&TAG_SEQ, out);
+                enc::dynamic(enc::list(Node::bin_write))(nodes, out)
+            }
+            Node::Prim { prim, args, annots } => {
+                let has_annots = !annots.is_empty();
+                let tag = match (args.len(), has_annots) {
+                    (0, false) => TAG_PRIM_0,
+                    (0, true) => TAG_PRIM_0_ANNOTS,
+                    (1, false) => TAG_PRIM_1,
+                    (1, true) => TAG_PRIM_1_ANNOTS,
+                    (2, false) => TAG_PRIM_2,
+                    (2, true) => TAG_PRIM_2_ANNOTS,
+                    _ => TAG_PRIM_N,
+                };
+                enc::put_byte(&tag, out);
+                enc::put_byte(&prim.tag(), out);
+                if tag == TAG_PRIM_N {
+                    enc::dynamic(enc::list(Node::bin_write))(args, out)?;
+                    return enc::string(annots.join(" "), out);
+                }
+                args.iter().try_for_each(|arg| arg.bin_write(out))?;
+                if has_annots {
+                    enc::string(annots.join(" "), out)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+std::thread_local! {
+    static DEPTH: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+fn node(max_depth: usize, input: NomInput<'_>) -> NomResult<'_, Node> {
+    nom_enc::bounded_depth(&DEPTH, max_depth, |input| node_contents(max_depth, input))(input)
+}
+
+fn node_contents(max_depth: usize, input: NomInput<'_>) -> NomResult<'_, Node> {
+    let node = move |input| node(max_depth, input);
+    let (input, tag) = nom_u8(input)?;
+    match tag {
+        TAG_INT => nom_enc::variant("Int", map(Zarith::nom_read, |z| Node::Int(z.0)))(input),
+        TAG_STRING => nom_enc::variant("String", map(nom_enc::string, Node::String))(input),
+        TAG_BYTES => {
+            nom_enc::variant("Bytes", map(nom_enc::dynamic(nom_enc::bytes), Node::Bytes))(input)
+        }
+        TAG_SEQ => {
+            nom_enc::variant("Seq", map(nom_enc::dynamic(nom_enc::list(node)), Node::Seq))(input)
+        }
+        TAG_PRIM_0 => {
+            nom_enc::variant("Prim", map(primitive, |prim| Node::prim(prim, vec![])))(input)
+        }
+        TAG_PRIM_0_ANNOTS => nom_enc::variant(
+            "Prim",
+            map(tuple((primitive, annots)), |(prim, annots)| {
+                Node::prim_annot(prim, vec![], annots)
+            }),
+        )(input),
+        TAG_PRIM_1 => nom_enc::variant(
+            "Prim",
+            map(tuple((primitive, node)), |(prim, arg)| {
+                Node::prim(prim, vec![arg])
+            }),
+        )(input),
+        TAG_PRIM_1_ANNOTS => nom_enc::variant(
+            "Prim",
+            map(tuple((primitive, node, annots)), |(prim, arg, annots)| {
+                Node::prim_annot(prim, vec![arg], annots)
+            }),
+        )(input),
+        TAG_PRIM_2 => nom_enc::variant(
+            "Prim",
+            map(tuple((primitive, node, node)), |(prim, arg1, arg2)| {
+                Node::prim(prim, vec![arg1, arg2])
+            }),
+        )(input),
+        TAG_PRIM_2_ANNOTS => nom_enc::variant(
+            "Prim",
+            map(
+                tuple((primitive, node, node, annots)),
+                |(prim, arg1, arg2, annots)| Node::prim_annot(prim, vec![arg1, arg2], annots),
+            ),
+        )(input),
+        TAG_PRIM_N => nom_enc::variant(
+            "Prim",
+            map(
+                tuple((primitive, nom_enc::dynamic(nom_enc::list(node)), annots)),
+                |(prim, args, annots)| Node::prim_annot(prim, args, annots),
+            ),
+        )(input),
+        _ => Err(Err::Error(DecodeError::invalid_tag(
+            input,
+            format!("0x{:.2X}", tag),
+        ))),
+    }
+}
+
+fn primitive(input: NomInput<'_>) -> NomResult<'_, Primitive> {
+    let (rest, tag) = nom_u8(input)?;
+    match Primitive::from_tag(tag) {
+        Ok(prim) => Ok((rest, prim)),
+        Err(_) => Err(Err::Error(DecodeError::invalid_tag(
+            input,
+            format!("0x{:.2X}", tag),
+        ))),
+    }
+}
+
+fn annots(input: NomInput<'_>) -> NomResult<'_, Vec<String>> {
+    map(nom_enc::string, |s| {
+        s.split(' ')
+            .filter(|a| !a.is_empty())
+            .map(str::to_string)
+            .collect()
+    })(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tezos_data_encoding::nom::error::convert_error;
+
+    fn round_trip(node: Node) {
+        let mut bytes = Vec::new();
+        node.bin_write(&mut bytes).unwrap();
+        let (rest, decoded) = Node::nom_read(&bytes).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(decoded, node);
+    }
+
+    #[test]
+    fn round_trip_all_tags() {
+        let annots = vec!["%a".to_string(), ":b".to_string()];
+        round_trip(Node::int(0));
+        round_trip(Node::int(-1_000_000_000_000i64));
+        round_trip(Node::string(""));
+        round_trip(Node::string("foo bar"));
+        round_trip(Node::bytes(vec![]));
+        round_trip(Node::bytes(vec![0xde, 0xad]));
+        round_trip(Node::seq(vec![]));
+        round_trip(Node::seq(vec![Node::int(1), Node::seq(vec![])]));
+        round_trip(Node::prim(Primitive::D_Unit, vec![]));
+        round_trip(Node::prim_annot(Primitive::T_unit, vec![], annots.clone()));
+        round_trip(Node::prim(Primitive::D_Some, vec![Node::int(1)]));
+        round_trip(Node::prim_annot(
+            Primitive::T_option,
+            vec![Node::prim(Primitive::T_nat, vec![])],
+            annots.clone(),
+        ));
+        round_trip(Node::prim(
+            Primitive::D_Pair,
+            vec![Node::int(1), Node::int(2)],
+        ));
+        round_trip(Node::prim_annot(
+            Primitive::T_pair,
+            vec![
+                Node::prim(Primitive::T_nat, vec![]),
+                Node::prim(Primitive::T_int, vec![]),
+            ],
+            annots.clone(),
+        ));
+        round_trip(Node::prim(
+            Primitive::D_Pair,
+            vec![Node::int(1), Node::int(2), Node::int(3)],
+        ));
+        round_trip(Node::prim_annot(
+            Primitive::K_view,
+            vec![
+                Node::string("v"),
+                Node::prim(Primitive::T_unit, vec![]),
+                Node::prim(Primitive::T_unit, vec![]),
+                Node::seq(vec![]),
+            ],
+            annots,
+        ));
+    }
+
+    #[test]
+    fn encoding() {
+        let mut bytes = Vec::new();
+        Node::prim_annot(Primitive::T_unit, vec![], vec!["%a".into(), "%b".into()])
+            .bin_write(&mut bytes)
+            .unwrap();
+        assert_eq!(hex::encode(bytes), "046c000000052561202562");
+    }
+
+    fn nested_some(depth: usize) -> Vec<u8> {
+        let mut bytes = [TAG_PRIM_1, Primitive::D_Some.tag()].repeat(depth - 1);
+        bytes.extend([TAG_PRIM_0, Primitive::D_Unit.tag()]);
+        bytes
+    }
+
+    #[test]
+    fn max_depth() {
+        let bytes = nested_some(MAX_DEPTH);
+        let (rest, _) = Node::nom_read(&bytes).unwrap();
+        assert!(rest.is_empty());
+
+        for depth in [MAX_DEPTH + 1, 1_000_000] {
+            let bytes = nested_some(depth);
+            match Node::nom_read(&bytes) {
+                Err(Err::Failure(e)) => assert!(e.is_depth_limit()),
+                res => panic!("unexpected result {:?}", res),
+            }
+        }
+
+        let bytes = nested_some(3);
+        assert!(Node::nom_read_with_max_depth(3, &bytes).is_ok());
+        assert!(Node::nom_read_with_max_depth(2, &bytes).is_err());
+    }
+
+    #[test]
+    fn display() {
+        let node = Node::seq(vec![
+            Node::prim_annot(
+                Primitive::K_parameter,
+                vec![Node::prim_annot(
+                    Primitive::T_pair,
+                    vec![
+                        Node::prim_annot(Primitive::T_nat, vec![], vec!["%a".into()]),
+                        Node::prim(Primitive::T_unit, vec![]),
+                    ],
+                    vec![":p".into()],
+                )],
+                vec![],
+            ),
+            Node::prim(
+                Primitive::I_PUSH,
+                vec![
+                    Node::prim(Primitive::T_string, vec![]),
+                    Node::string("a\"\\\n"),
+                ],
+            ),
+            Node::prim(
+                Primitive::I_PUSH,
+                vec![Node::prim(Primitive::T_int, vec![]), Node::int(-3)],
+            ),
+            Node::bytes(vec![0x00, 0xab]),
+            Node::seq(vec![]),
+        ]);
+        assert_eq!(
+            node.to_string(),
+            r#"{ parameter (pair :p (nat %a) unit) ; PUSH string "a\"\\\n" ; PUSH int -3 ; 0x00ab ; {} }"#
+        );
+        assert_eq!(
+            format!("{:#}", node),
+            r#"{ parameter (pair :p (nat %a) unit) ;
+  PUSH string "a\"\\\n" ;
+  PUSH int -3 ;
+  0x00ab ;
+  {} }"#
+        );
+
+        let code = Node::prim(
+            Primitive::K_code,
+            vec![Node::seq(vec![
+                Node::prim(Primitive::I_CDR, vec![]),
+                Node::prim(
+                    Primitive::I_NIL,
+                    vec![Node::prim(Primitive::T_operation, vec![])],
+                ),
+            ])],
+        );
+        assert_eq!(
+            format!("{:#}", Node::seq(vec![code])),
+            "{ code { CDR ;\n         NIL operation } }"
+        );
+    }
+
+    #[test]
+    fn invalid_tags() {
+        for bytes in [&[0x0b][..], &[0x03, 0xff][..]] {
+            let err = match Node::nom_read(bytes) {
+                Err(Err::Error(e)) => e,
+                res => panic!("unexpected result {:?}", res),
+            };
+            assert!(convert_error(bytes, err).contains("caused by invalid tag"));
+        }
+    }
+}
@@ -0,0 +1,140 @@
+// SPDX-FileCopyrightText: 2023 TriliTech <contact@trili.tech>
+// SPDX-License-Identifier: MIT
+
+//! Packing of Micheline values, as done by the `PACK` instruction, and the
+//! `expr` hashes derived from packed values.
+
+use crypto::blake2b::{self, Blake2bError};
+use crypto::hash::{FromBytesError, ScriptExprHash};
+use tezos_data_encoding::enc::{BinError, BinWriter};
+use tezos_data_encoding::nom::error::convert_error;
+use tezos_data_encoding::nom::NomReader;
+use thiserror::Error;
+
+use crate::node::Node;
+
+/// Prefix of packed Micheline values.
+pub const PACK_PREFIX: u8 = 0x05;
+
+/// Error packing a Micheline value.
+#[derive(Debug, Error)]
+pub enum PackError {
+    #[error("cannot encode value: {0}")]
+    Encoding(#[from] BinError),
+    #[error("cannot hash packed value: {0}")]
+    Hash(#[from] Blake2bError),
+    #[error("invalid hash: {0}")]
+    FromBytes(#[from] FromBytesError),
+}
+
+/// Error unpacking a Micheline value.
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum UnpackError {
+    #[error("packed value does not start with 0x05")]
+    MissingPrefix,
+    #[error("cannot decode value: {0}")]
+    Decoding(String),
+    #[error("{0} trailing bytes after packed value")]
+    TrailingBytes(usize),
+}
+
+/// Packs the value the same way as the Michelson `PACK` instruction.
+///
+/// The value is packed as is, and matches `PACK` only if it is in optimized form, since
+/// `PACK` first normalizes values of some types: addresses, keys, key hashes, signatures
+/// and chain ids to bytes, timestamps to ints, and pairs to right combs. Values in
+/// readable form, such as a `"tz1..."` address string, give different bytes and hashes.
+pub fn pack(node: &Node) -> Result<Vec<u8>, PackError> {
+    let mut out = vec![PACK_PREFIX];
+    node.bin_write(&mut out)?;
+    Ok(out)
+}
+
+/// Unpacks a value packed with [pack] (or with `PACK`).
+pub fn unpack(bytes: &[u8]) -> Result<Node, UnpackError> {
+    match bytes.split_first() {
+        Some((&PACK_PREFIX, input)) => decode(input),
+        _ => Err(UnpackError::MissingPrefix),
+    }
+}
+
+/// Decodes an expression spanning the whole of `input`.
+pub(crate) fn decode(input: &[u8]) -> Result<Node, UnpackError> {
+    let (rest, node) = Node::nom_read(input).map_err(|e| match e {
+        nom::Err::Error(e) | nom::Err::Failure(e) => UnpackError::Decoding(convert_error(input, e)),
+        nom::Err::Incomplete(_) => UnpackError::Decoding("incomplete input".to_string()),
+    })?;
+    if rest.is_empty() {
+        Ok(node)
+    } else {
+        Err(UnpackError::TrailingBytes(rest.len()))
+    }
+}
+
+/// Script expression hash of the value: blake2b-256 of the packed value.
+///
+/// As for [pack], the value must be in optimized form.
+///
+/// This is the hash under which values are indexed, e.g. `big_map` keys.
+pub fn expr_hash(node: &Node) -> Result<ScriptExprHash, PackError> {
+    let digest = blake2b::digest_256(&pack(node)?)?;
+    Ok(ScriptExprHash::try_from(digest)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitive::Primitive;
+    use crypto::hash::HashTrait;
+
+    fn assert_pack(node: Node, expected: &str) {
+        let packed = pack(&node).unwrap();
+        assert_eq!(hex::encode(&packed), expected);
+        assert_eq!(unpack(&packed), Ok(node));
+    }
+
+    #[test]
+    fn pack_values() {
+        assert_pack(Node::int(1), "050001");
+        assert_pack(Node::int(-1), "050041");
+        assert_pack(Node::int(-64), "0500c001");
+        assert_pack(Node::string("foo"), "050100000003666f6f");
+        assert_pack(Node::bytes(vec![0xab, 0xcd]), "050a00000002abcd");
+        assert_pack(Node::seq(vec![]), "050200000000");
+        assert_pack(Node::prim(Primitive::D_Unit, vec![]), "05030b");
+        assert_pack(
+            Node::prim(Primitive::D_Pair, vec![Node::int(1), Node::int(2)]),
+            "05070700010002",
+        );
+    }
+
+    #[test]
+    fn unpack_errors() {
+        assert_eq!(unpack(&[]), Err(UnpackError::MissingPrefix));
+        assert_eq!(unpack(&[0x00, 0x00, 0x01]), Err(UnpackError::MissingPrefix));
+        assert_eq!(
+            unpack(&[0x05, 0x00, 0x01, 0x00]),
+            Err(UnpackError::TrailingBytes(1))
+        );
+        assert!(matches!(
+            unpack(&[0x05, 0x0b]),
+            Err(UnpackError::Decoding(_))
+        ));
+    }
+
+    #[test]
+    fn expr_hash_of_packed() {
+        // hashes computed by octez-client
+        for (value, hash) in [
+            (0, "exprtZBwZUeYYYfUs9B9Rg2ywHezVHnCCnmF9WsDQVrs582dSK63dC"),
+            (1, "expru2dKqDfZG8hu4wNGkiyunvq2hdSKuVYtcKta7BWP6Q18oNxKjS"),
+        ] {
+            let node = Node::int(value);
+            assert_eq!(
+                hex::encode(pack(&node).unwrap()),
+                format!("0500{:02x}", value)
+            );
+            assert_eq!(expr_hash(&node).unwrap().to_b58check(), hash);
+        }
+    }
+}
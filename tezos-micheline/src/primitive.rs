@@ -0,0 +1,267 @@
+// SPDX-FileCopyrightText: 2023 TriliTech <contact@trili.tech>
+// SPDX-License-Identifier: MIT
+
+//! Michelson primitives, as identified in the binary encoding of Micheline.
+//!
+//! The tag of each primitive is its position in the octez primitive table, which is
+//! append-only: new primitives are only ever added at the end.
+
+use std::fmt;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+/// Error returned when a primitive name or tag is not known.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum PrimitiveError {
+    #[error("unknown primitive name `{0}`")]
+    UnknownName(String),
+    #[error("unknown primitive tag `{0}`")]
+    UnknownTag(u8),
+}
+
+macro_rules! primitives {
+    ($($variant:ident = $tag:literal => $name:literal,)*) => {
+        /// Michelson primitive: keyword, data constructor, instruction or type.
+        ///
+        /// Variants are prefixed the same way as in octez: `K_` for keywords, `D_` for data
+        /// constructors, `I_` for instructions, `T_` for types and `H_` for constants.
+        #[allow(non_camel_case_types)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub enum Primitive {
+            $($variant,)*
+        }
+
+        impl Primitive {
+            /// All primitives, in tag order.
+            pub const ALL: &'static [Primitive] = &[$(Primitive::$variant,)*];
+
+            /// Tag of the primitive in the binary encoding.
+            pub fn tag(self) -> u8 {
+                match self {
+                    $(Primitive::$variant => $tag,)*
+                }
+            }
+
+            /// Primitive with the given binary tag.
+            pub fn from_tag(tag: u8) -> Result<Self, PrimitiveError> {
+                match tag {
+                    $($tag => Ok(Primitive::$variant),)*
+                    _ => Err(PrimitiveError::UnknownTag(tag)),
+                }
+            }
+
+            /// Name of the primitive, as written in Michelson source.
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(Primitive::$variant => $name,)*
+                }
+            }
+        }
+
+        impl FromStr for Primitive {
+            type Err = PrimitiveError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                    $($name => Ok(Primitive::$variant),)*
+                    _ => Err(PrimitiveError::UnknownName(s.to_string())),
+                }
+            }
+        }
+    };
+}
+
+primitives! {
+    K_parameter = 0x00 => "parameter",
+    K_storage = 0x01 => "storage",
+    K_code = 0x02 => "code",
+    D_False = 0x03 => "False",
+    D_Elt = 0x04 => "Elt",
+    D_Left = 0x05 => "Left",
+    D_None = 0x06 => "None",
+    D_Pair = 0x07 => "Pair",
+    D_Right = 0x08 => "Right",
+    D_Some = 0x09 => "Some",
+    D_True = 0x0a => "True",
+    D_Unit = 0x0b => "Unit",
+    I_PACK = 0x0c => "PACK",
+    I_UNPACK = 0x0d => "UNPACK",
+    I_BLAKE2B = 0x0e => "BLAKE2B",
+    I_SHA256 = 0x0f => "SHA256",
+    I_SHA512 = 0x10 => "SHA512",
+    I_ABS = 0x11 => "ABS",
+    I_ADD = 0x12 => "ADD",
+    I_AMOUNT = 0x13 => "AMOUNT",
+    I_AND = 0x14 => "AND",
+    I_BALANCE = 0x15 => "BALANCE",
+    I_CAR = 0x16 => "CAR",
+    I_CDR = 0x17 => "CDR",
+    I_CHECK_SIGNATURE = 0x18 => "CHECK_SIGNATURE",
+    I_COMPARE = 0x19 => "COMPARE",
+    I_CONCAT = 0x1a => "CONCAT",
+    I_CONS = 0x1b => "CONS",
+    I_CREATE_ACCOUNT = 0x1c => "CREATE_ACCOUNT",
+    I_CREATE_CONTRACT = 0x1d => "CREATE_CONTRACT",
+    I_IMPLICIT_ACCOUNT = 0x1e => "IMPLICIT_ACCOUNT",
+    I_DIP = 0x1f => "DIP",
+    I_DROP = 0x20 => "DROP",
+    I_DUP = 0x21 => "DUP",
+    I_EDIV = 0x22 => "EDIV",
+    I_EMPTY_MAP = 0x23 => "EMPTY_MAP",
+    I_EMPTY_SET = 0x24 => "EMPTY_SET",
+    I_EQ = 0x25 => "EQ",
+    I_EXEC = 0x26 => "EXEC",
+    I_FAILWITH = 0x27 => "FAILWITH",
+    I_GE = 0x28 => "GE",
+    I_GET = 0x29 => "GET",
+    I_GT = 0x2a => "GT",
+    I_HASH_KEY = 0x2b => "HASH_KEY",
+    I_IF = 0x2c => "IF",
+    I_IF_CONS = 0x2d => "IF_CONS",
+    I_IF_LEFT = 0x2e => "IF_LEFT",
+    I_IF_NONE = 0x2f => "IF_NONE",
+    I_INT = 0x30 => "INT",
+    I_LAMBDA = 0x31 => "LAMBDA",
+    I_LE = 0x32 => "LE",
+    I_LEFT = 0x33 => "LEFT",
+    I_LOOP = 0x34 => "LOOP",
+    I_LSL = 0x35 => "LSL",
+    I_LSR = 0x36 => "LSR",
+    I_LT = 0x37 => "LT",
+    I_MAP = 0x38 => "MAP",
+    I_MEM = 0x39 => "MEM",
+    I_MUL = 0x3a => "MUL",
+    I_NEG = 0x3b => "NEG",
+    I_NEQ = 0x3c => "NEQ",
+    I_NIL = 0x3d => "NIL",
+    I_NONE = 0x3e => "NONE",
+    I_NOT = 0x3f => "NOT",
+    I_NOW = 0x40 => "NOW",
+    I_OR = 0x41 => "OR",
+    I_PAIR = 0x42 => "PAIR",
+    I_PUSH = 0x43 => "PUSH",
+    I_RIGHT = 0x44 => "RIGHT",
+    I_SIZE = 0x45 => "SIZE",
+    I_SOME = 0x46 => "SOME",
+    I_SOURCE = 0x47 => "SOURCE",
+    I_SENDER = 0x48 => "SENDER",
+    I_SELF = 0x49 => "SELF",
+    I_STEPS_TO_QUOTA = 0x4a => "STEPS_TO_QUOTA",
+    I_SUB = 0x4b => "SUB",
+    I_SWAP = 0x4c => "SWAP",
+    I_TRANSFER_TOKENS = 0x4d => "TRANSFER_TOKENS",
+    I_SET_DELEGATE = 0x4e => "SET_DELEGATE",
+    I_UNIT = 0x4f => "UNIT",
+    I_UPDATE = 0x50 => "UPDATE",
+    I_XOR = 0x51 => "XOR",
+    I_ITER = 0x52 => "ITER",
+    I_LOOP_LEFT = 0x53 => "LOOP_LEFT",
+    I_ADDRESS = 0x54 => "ADDRESS",
+    I_CONTRACT = 0x55 => "CONTRACT",
+    I_ISNAT = 0x56 => "ISNAT",
+    I_CAST = 0x57 => "CAST",
+    I_RENAME = 0x58 => "RENAME",
+    T_bool = 0x59 => "bool",
+    T_contract = 0x5a => "contract",
+    T_int = 0x5b => "int",
+    T_key = 0x5c => "key",
+    T_key_hash = 0x5d => "key_hash",
+    T_lambda = 0x5e => "lambda",
+    T_list = 0x5f => "list",
+    T_map = 0x60 => "map",
+    T_big_map = 0x61 => "big_map",
+    T_nat = 0x62 => "nat",
+    T_option = 0x63 => "option",
+    T_or = 0x64 => "or",
+    T_pair = 0x65 => "pair",
+    T_set = 0x66 => "set",
+    T_signature = 0x67 => "signature",
+    T_string = 0x68 => "string",
+    T_bytes = 0x69 => "bytes",
+    T_mutez = 0x6a => "mutez",
+    T_timestamp = 0x6b => "timestamp",
+    T_unit = 0x6c => "unit",
+    T_operation = 0x6d => "operation",
+    T_address = 0x6e => "address",
+    I_SLICE = 0x6f => "SLICE",
+    I_DIG = 0x70 => "DIG",
+    I_DUG = 0x71 => "DUG",
+    I_EMPTY_BIG_MAP = 0x72 => "EMPTY_BIG_MAP",
+    I_APPLY = 0x73 => "APPLY",
+    T_chain_id = 0x74 => "chain_id",
+    I_CHAIN_ID = 0x75 => "CHAIN_ID",
+    I_LEVEL = 0x76 => "LEVEL",
+    I_SELF_ADDRESS = 0x77 => "SELF_ADDRESS",
+    T_never = 0x78 => "never",
+    I_NEVER = 0x79 => "NEVER",
+    I_UNPAIR = 0x7a => "UNPAIR",
+    I_VOTING_POWER = 0x7b => "VOTING_POWER",
+    I_TOTAL_VOTING_POWER = 0x7c => "TOTAL_VOTING_POWER",
+    I_KECCAK = 0x7d => "KECCAK",
+    I_SHA3 = 0x7e => "SHA3",
+    I_PAIRING_CHECK = 0x7f => "PAIRING_CHECK",
+    T_bls12_381_g1 = 0x80 => "bls12_381_g1",
+    T_bls12_381_g2 = 0x81 => "bls12_381_g2",
+    T_bls12_381_fr = 0x82 => "bls12_381_fr",
+    T_sapling_state = 0x83 => "sapling_state",
+    T_sapling_transaction_deprecated = 0x84 => "sapling_transaction_deprecated",
+    I_SAPLING_EMPTY_STATE = 0x85 => "SAPLING_EMPTY_STATE",
+    I_SAPLING_VERIFY_UPDATE = 0x86 => "SAPLING_VERIFY_UPDATE",
+    T_ticket = 0x87 => "ticket",
+    I_TICKET_DEPRECATED = 0x88 => "TICKET_DEPRECATED",
+    I_READ_TICKET = 0x89 => "READ_TICKET",
+    I_SPLIT_TICKET = 0x8a => "SPLIT_TICKET",
+    I_JOIN_TICKETS = 0x8b => "JOIN_TICKETS",
+    I_GET_AND_UPDATE = 0x8c => "GET_AND_UPDATE",
+    T_chest = 0x8d => "chest",
+    T_chest_key = 0x8e => "chest_key",
+    I_OPEN_CHEST = 0x8f => "OPEN_CHEST",
+    I_VIEW = 0x90 => "VIEW",
+    K_view = 0x91 => "view",
+    H_constant = 0x92 => "constant",
+    I_SUB_MUTEZ = 0x93 => "SUB_MUTEZ",
+    T_tx_rollup_l2_address = 0x94 => "tx_rollup_l2_address",
+    I_MIN_BLOCK_TIME = 0x95 => "MIN_BLOCK_TIME",
+    T_sapling_transaction = 0x96 => "sapling_transaction",
+    I_EMIT = 0x97 => "EMIT",
+    D_Lambda_rec = 0x98 => "Lambda_rec",
+    I_LAMBDA_REC = 0x99 => "LAMBDA_REC",
+    I_TICKET = 0x9a => "TICKET",
+    I_BYTES = 0x9b => "BYTES",
+    I_NAT = 0x9c => "NAT",
+    D_Ticket = 0x9d => "Ticket",
+    I_IS_IMPLICIT_ACCOUNT = 0x9e => "IS_IMPLICIT_ACCOUNT",
+    I_INDEX_ADDRESS = 0x9f => "INDEX_ADDRESS",
+    I_GET_TOTAL_VOTING_POWER = 0xa0 => "GET_TOTAL_VOTING_POWER",
+}
+
+impl fmt::Display for Primitive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_are_contiguous() {
+        for (i, prim) in Primitive::ALL.iter().enumerate() {
+            assert_eq!(prim.tag() as usize, i);
+            assert_eq!(Primitive::from_tag(prim.tag()), Ok(*prim));
+            assert_eq!(prim.name().parse::<Primitive>(), Ok(*prim));
+        }
+    }
+
+    #[test]
+    fn unknown() {
+        let next = Primitive::ALL.len() as u8;
+        assert_eq!(
+            Primitive::from_tag(next),
+            Err(PrimitiveError::UnknownTag(next))
+        );
+        assert!("FOO".parse::<Primitive>().is_err());
+    }
+}
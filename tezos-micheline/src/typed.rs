@@ -0,0 +1,1144 @@
+// SPDX-FileCopyrightText: 2023 TriliTech <contact@trili.tech>
+// SPDX-License-Identifier: MIT
+
+//! Typed Michelson AST: types, data, instructions and scripts.
+//!
+//! Values are converted from raw Micheline [Node]s with `TryFrom`, and back with `From`,
+//! which also gives their formatting in Michelson concrete syntax.
+//!
+//! Annotations are dropped, except those carrying meaning: field annotations of types,
+//! which also name entrypoints, and the entrypoint or tag annotations of `CONTRACT`,
+//! `SELF` and `EMIT`.
+//!
+//! Global constants are kept as `Constant` references to their hash: expanding them needs
+//! their values, which are only known to the context they are registered in.
+//!
+//! ```rust
+//! use tezos_micheline::typed::{Instruction, Type};
+//! use tezos_micheline::{Node, Primitive};
+//!
+//! let node = Node::prim(Primitive::I_NIL, vec![Node::prim(Primitive::T_operation, vec![])]);
+//! let instr = Instruction::try_from(&node).unwrap();
+//! assert_eq!(instr, Instruction::Nil(Type::Operation));
+//! assert_eq!(instr.to_string(), "NIL operation");
+//! ```
+
+use std::fmt;
+
+use num_bigint::BigInt;
+use thiserror::Error;
+
+use crate::node::Node;
+use crate::primitive::Primitive;
+
+/// Error converting a raw Micheline expression to the typed AST.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum FromNodeError {
+    #[error("expected {expected}, found {found}")]
+    Unexpected {
+        expected: &'static str,
+        found: String,
+    },
+    #[error("unexpected number of arguments for `{prim}`: {count}")]
+    Arity { prim: Primitive, count: usize },
+    #[error("number out of range: {0}")]
+    OutOfRange(BigInt),
+    #[error("missing or duplicate `{0}` section")]
+    Section(Primitive),
+}
+
+/// Michelson type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Type {
+    Unit,
+    Bool,
+    Int,
+    Nat,
+    String,
+    Bytes,
+    Mutez,
+    Timestamp,
+    Address,
+    Key,
+    KeyHash,
+    Signature,
+    ChainId,
+    Operation,
+    Never,
+    Bls12381G1,
+    Bls12381G2,
+    Bls12381Fr,
+    Chest,
+    ChestKey,
+    TxRollupL2Address,
+    Option(Box<Type>),
+    List(Box<Type>),
+    Set(Box<Type>),
+    Contract(Box<Type>),
+    Ticket(Box<Type>),
+    /// Pair of two or more types, `pair a b c` being `pair a (pair b c)`.
+    Pair(Vec<Type>),
+    Or(Box<Type>, Box<Type>),
+    Map(Box<Type>, Box<Type>),
+    BigMap(Box<Type>, Box<Type>),
+    Lambda(Box<Type>, Box<Type>),
+    SaplingState(u16),
+    SaplingTransaction(u16),
+    SaplingTransactionDeprecated(u16),
+    /// Type with a field annotation, e.g. `nat %amount`, without the `%`.
+    Field(String, Box<Type>),
+    /// Global constant, `constant "expr..."`, by its script expression hash.
+    Constant(String),
+}
+
+/// Michelson data, typed only as far as the raw expression tells.
+///
+/// Lists, sets and empty maps are all [Data::Seq], non-empty maps are [Data::Map].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Data {
+    Int(BigInt),
+    String(String),
+    Bytes(Vec<u8>),
+    Unit,
+    Bool(bool),
+    /// Pair of two or more values, `Pair a b c` being `Pair a (Pair b c)`.
+    Pair(Vec<Data>),
+    Left(Box<Data>),
+    Right(Box<Data>),
+    Some(Box<Data>),
+    None,
+    Seq(Vec<Data>),
+    Map(Vec<(Data, Data)>),
+    Lambda(Vec<Instruction>),
+    LambdaRec(Vec<Instruction>),
+    /// `Ticket ticketer type contents amount`.
+    Ticket(Box<Data>, Type, Box<Data>, Box<Data>),
+    /// Global constant, `constant "expr..."`, by its script expression hash.
+    Constant(String),
+}
+
+/// Michelson instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Instruction {
+    /// Instruction without arguments, e.g. `ADD` or `SWAP`.
+    Simple(Primitive),
+    Seq(Vec<Instruction>),
+    Drop(Option<u16>),
+    Dup(Option<u16>),
+    Dig(u16),
+    Dug(u16),
+    Pair(Option<u16>),
+    Unpair(Option<u16>),
+    Get(Option<u16>),
+    Update(Option<u16>),
+    Dip(Option<u16>, Vec<Instruction>),
+    Push(Type, Data),
+    Nil(Type),
+    None(Type),
+    Left(Type),
+    Right(Type),
+    EmptySet(Type),
+    EmptyMap(Type, Type),
+    EmptyBigMap(Type, Type),
+    Unpack(Type),
+    Cast(Type),
+    /// `CONTRACT`, with an optional entrypoint.
+    Contract(Option<String>, Type),
+    /// `SELF`, with an optional entrypoint.
+    SelfContract(Option<String>),
+    /// `EMIT`, with optional tag and type of the event.
+    Emit(Option<String>, Option<Type>),
+    View(String, Type),
+    SaplingEmptyState(u16),
+    Lambda(Type, Type, Vec<Instruction>),
+    LambdaRec(Type, Type, Vec<Instruction>),
+    If(Vec<Instruction>, Vec<Instruction>),
+    IfNone(Vec<Instruction>, Vec<Instruction>),
+    IfLeft(Vec<Instruction>, Vec<Instruction>),
+    IfCons(Vec<Instruction>, Vec<Instruction>),
+    Loop(Vec<Instruction>),
+    LoopLeft(Vec<Instruction>),
+    Iter(Vec<Instruction>),
+    Map(Vec<Instruction>),
+    CreateContract(Box<Script>),
+    /// Global constant, `constant "expr..."`, by its script expression hash.
+    Constant(String),
+}
+
+/// On-chain view of a script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct View {
+    pub name: String,
+    pub input: Type,
+    pub output: Type,
+    pub code: Vec<Instruction>,
+}
+
+/// Michelson script: parameter and storage types, code and views.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Script {
+    pub parameter: Type,
+    pub storage: Type,
+    pub code: Vec<Instruction>,
+    pub views: Vec<View>,
+}
+
+macro_rules! simple {
+    ($of_prim:ident, $to_prim:ident, $ty:ident { $($variant:ident => $prim:ident,)* }) => {
+        fn $of_prim(prim: Primitive) -> Option<$ty> {
+            match prim {
+                $(Primitive::$prim => Some($ty::$variant),)*
+                _ => None,
+            }
+        }
+
+        fn $to_prim(value: &$ty) -> Option<Primitive> {
+            match value {
+                $($ty::$variant => Some(Primitive::$prim),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+simple!(simple_type, simple_type_prim, Type {
+    Unit => T_unit,
+    Bool => T_bool,
+    Int => T_int,
+    Nat => T_nat,
+    String => T_string,
+    Bytes => T_bytes,
+    Mutez => T_mutez,
+    Timestamp => T_timestamp,
+    Address => T_address,
+    Key => T_key,
+    KeyHash => T_key_hash,
+    Signature => T_signature,
+    ChainId => T_chain_id,
+    Operation => T_operation,
+    Never => T_never,
+    Bls12381G1 => T_bls12_381_g1,
+    Bls12381G2 => T_bls12_381_g2,
+    Bls12381Fr => T_bls12_381_fr,
+    Chest => T_chest,
+    ChestKey => T_chest_key,
+    TxRollupL2Address => T_tx_rollup_l2_address,
+});
+
+/// Instructions taking neither arguments nor meaningful annotations.
+const SIMPLE_INSTRUCTIONS: &[Primitive] = {
+    use Primitive::*;
+    &[
+        I_ABS,
+        I_ADD,
+        I_ADDRESS,
+        I_AMOUNT,
+        I_AND,
+        I_APPLY,
+        I_BALANCE,
+        I_BLAKE2B,
+        I_BYTES,
+        I_CAR,
+        I_CDR,
+        I_CHAIN_ID,
+        I_CHECK_SIGNATURE,
+        I_COMPARE,
+        I_CONCAT,
+        I_CONS,
+        I_EDIV,
+        I_EQ,
+        I_EXEC,
+        I_FAILWITH,
+        I_GE,
+        I_GET_AND_UPDATE,
+        I_GET_TOTAL_VOTING_POWER,
+        I_GT,
+        I_HASH_KEY,
+        I_IMPLICIT_ACCOUNT,
+        I_INDEX_ADDRESS,
+        I_INT,
+        I_IS_IMPLICIT_ACCOUNT,
+        I_ISNAT,
+        I_JOIN_TICKETS,
+        I_KECCAK,
+        I_LE,
+        I_LEVEL,
+        I_LSL,
+        I_LSR,
+        I_LT,
+        I_MIN_BLOCK_TIME,
+        I_MUL,
+        I_NAT,
+        I_NEG,
+        I_NEQ,
+        I_NEVER,
+        I_NOT,
+        I_NOW,
+        I_OPEN_CHEST,
+        I_OR,
+        I_PACK,
+        I_PAIRING_CHECK,
+        I_READ_TICKET,
+        I_RENAME,
+        I_SAPLING_VERIFY_UPDATE,
+        I_SELF_ADDRESS,
+        I_SENDER,
+        I_SET_DELEGATE,
+        I_SHA256,
+        I_SHA3,
+        I_SHA512,
+        I_SIZE,
+        I_SLICE,
+        I_SOME,
+        I_SOURCE,
+        I_SPLIT_TICKET,
+        I_SUB,
+        I_SUB_MUTEZ,
+        I_SWAP,
+        I_TICKET,
+        I_TICKET_DEPRECATED,
+        I_TOTAL_VOTING_POWER,
+        I_TRANSFER_TOKENS,
+        I_UNIT,
+        I_VOTING_POWER,
+        I_XOR,
+    ]
+};
+
+fn describe(node: &Node) -> String {
+    match node {
+        Node::Int(_) => "integer".to_string(),
+        Node::String(_) => "string".to_string(),
+        Node::Bytes(_) => "bytes".to_string(),
+        Node::Seq(_) => "sequence".to_string(),
+        Node::Prim { prim, .. } => format!("`{}`", prim),
+    }
+}
+
+fn unexpected<T>(expected: &'static str, node: &Node) -> Result<T, FromNodeError> {
+    Err(FromNodeError::Unexpected {
+        expected,
+        found: describe(node),
+    })
+}
+
+fn arity<const N: usize>(prim: Primitive, args: &[Node]) -> Result<&[Node; N], FromNodeError> {
+    args.try_into().map_err(|_| FromNodeError::Arity {
+        prim,
+        count: args.len(),
+    })
+}
+
+fn annotation(annots: &[String], prefix: char) -> Option<String> {
+    annots
+        .iter()
+        .find_map(|a| a.strip_prefix(prefix))
+        .map(str::to_string)
+}
+
+fn small_nat(node: &Node) -> Result<u16, FromNodeError> {
+    match node {
+        Node::Int(i) => u16::try_from(i).map_err(|_| FromNodeError::OutOfRange(i.clone())),
+        _ => unexpected("natural number", node),
+    }
+}
+
+fn string(node: &Node) -> Result<String, FromNodeError> {
+    match node {
+        Node::String(s) => Ok(s.clone()),
+        _ => unexpected("string", node),
+    }
+}
+
+fn constant(args: &[Node]) -> Result<String, FromNodeError> {
+    let [hash] = arity(Primitive::H_constant, args)?;
+    string(hash)
+}
+
+fn ty(node: &Node) -> Result<Box<Type>, FromNodeError> {
+    Type::try_from(node).map(Box::new)
+}
+
+fn code(node: &Node) -> Result<Vec<Instruction>, FromNodeError> {
+    match node {
+        Node::Seq(nodes) => nodes.iter().map(Instruction::try_from).collect(),
+        _ => unexpected("sequence of instructions", node),
+    }
+}
+
+fn int(n: u16) -> Node {
+    Node::int(n)
+}
+
+fn seq(code: &[Instruction]) -> Node {
+    Node::seq(code.iter().map(Node::from).collect())
+}
+
+impl TryFrom<&Node> for Type {
+    type Error = FromNodeError;
+
+    fn try_from(node: &Node) -> Result<Self, Self::Error> {
+        use Primitive::*;
+
+        let (prim, args, annots) = match node {
+            Node::Prim { prim, args, annots } => (*prim, args.as_slice(), annots),
+            _ => return unexpected("type", node),
+        };
+        let ty = match prim {
+            _ if simple_type(prim).is_some() => {
+                arity::<0>(prim, args)?;
+                simple_type(prim).unwrap()
+            }
+            T_option | T_list | T_set | T_contract | T_ticket => {
+                let [t] = arity(prim, args)?;
+                let t = ty(t)?;
+                match prim {
+                    T_option => Type::Option(t),
+                    T_list => Type::List(t),
+                    T_set => Type::Set(t),
+                    T_contract => Type::Contract(t),
+                    _ => Type::Ticket(t),
+                }
+            }
+            T_or | T_map | T_big_map | T_lambda => {
+                let [l, r] = arity(prim, args)?;
+                let (l, r) = (ty(l)?, ty(r)?);
+                match prim {
+                    T_or => Type::Or(l, r),
+                    T_map => Type::Map(l, r),
+                    T_big_map => Type::BigMap(l, r),
+                    _ => Type::Lambda(l, r),
+                }
+            }
+            T_pair if args.len() >= 2 => {
+                Type::Pair(args.iter().map(Type::try_from).collect::<Result<_, _>>()?)
+            }
+            T_pair => {
+                return Err(FromNodeError::Arity {
+                    prim,
+                    count: args.len(),
+                })
+            }
+            T_sapling_state | T_sapling_transaction | T_sapling_transaction_deprecated => {
+                let [n] = arity(prim, args)?;
+                let n = small_nat(n)?;
+                match prim {
+                    T_sapling_state => Type::SaplingState(n),
+                    T_sapling_transaction => Type::SaplingTransaction(n),
+                    _ => Type::SaplingTransactionDeprecated(n),
+                }
+            }
+            H_constant => Type::Constant(constant(args)?),
+            _ => return unexpected("type", node),
+        };
+        Ok(match annotation(annots, '%') {
+            Some(field) => Type::Field(field, Box::new(ty)),
+            None => ty,
+        })
+    }
+}
+
+impl From<&Type> for Node {
+    fn from(ty: &Type) -> Self {
+        use Primitive::*;
+
+        if let Some(prim) = simple_type_prim(ty) {
+            return Node::prim(prim, vec![]);
+        }
+        let (prim, args) = match ty {
+            Type::Option(t) => (T_option, vec![t.as_ref().into()]),
+            Type::List(t) => (T_list, vec![t.as_ref().into()]),
+            Type::Set(t) => (T_set, vec![t.as_ref().into()]),
+            Type::Contract(t) => (T_contract, vec![t.as_ref().into()]),
+            Type::Ticket(t) => (T_ticket, vec![t.as_ref().into()]),
+            Type::Pair(ts) => (T_pair, ts.iter().map(Node::from).collect()),
+            Type::Or(l, r) => (T_or, vec![l.as_ref().into(), r.as_ref().into()]),
+            Type::Map(k, v) => (T_map, vec![k.as_ref().into(), v.as_ref().into()]),
+            Type::BigMap(k, v) => (T_big_map, vec![k.as_ref().into(), v.as_ref().into()]),
+            Type::Lambda(a, r) => (T_lambda, vec![a.as_ref().into(), r.as_ref().into()]),
+            Type::SaplingState(n) => (T_sapling_state, vec![int(*n)]),
+            Type::SaplingTransaction(n) => (T_sapling_transaction, vec![int(*n)]),
+            Type::SaplingTransactionDeprecated(n) => {
+                (T_sapling_transaction_deprecated, vec![int(*n)])
+            }
+            Type::Field(field, t) => {
+                let mut node = Node::from(t.as_ref());
+                if let Node::Prim { annots, .. } = &mut node {
+                    annots.insert(0, format!("%{}", field));
+                }
+                return node;
+            }
+            Type::Constant(hash) => (H_constant, vec![Node::string(hash.as_str())]),
+            _ => unreachable!("simple types are handled above"),
+        };
+        Node::prim(prim, args)
+    }
+}
+
+impl TryFrom<&Node> for Data {
+    type Error = FromNodeError;
+
+    fn try_from(node: &Node) -> Result<Self, Self::Error> {
+        use Primitive::*;
+
+        let (prim, args) = match node {
+            Node::Int(i) => return Ok(Data::Int(i.clone())),
+            Node::String(s) => return Ok(Data::String(s.clone())),
+            Node::Bytes(b) => return Ok(Data::Bytes(b.clone())),
+            Node::Seq(nodes) => return data_seq(nodes),
+            Node::Prim { prim, args, .. } => (*prim, args.as_slice()),
+        };
+        let data = |node| Data::try_from(node).map(Box::new);
+        Ok(match prim {
+            D_Unit | D_True | D_False | D_None => {
+                arity::<0>(prim, args)?;
+                match prim {
+                    D_Unit => Data::Unit,
+                    D_True => Data::Bool(true),
+                    D_False => Data::Bool(false),
+                    _ => Data::None,
+                }
+            }
+            D_Left | D_Right | D_Some => {
+                let [d] = arity(prim, args)?;
+                let d = data(d)?;
+                match prim {
+                    D_Left => Data::Left(d),
+                    D_Right => Data::Right(d),
+                    _ => Data::Some(d),
+                }
+            }
+            D_Pair if args.len() >= 2 => {
+                Data::Pair(args.iter().map(Data::try_from).collect::<Result<_, _>>()?)
+            }
+            D_Pair => {
+                return Err(FromNodeError::Arity {
+                    prim,
+                    count: args.len(),
+                })
+            }
+            D_Lambda_rec => {
+                let [c] = arity(prim, args)?;
+                Data::LambdaRec(code(c)?)
+            }
+            D_Ticket => {
+                let [ticketer, t, contents, amount] = arity(prim, args)?;
+                Data::Ticket(
+                    data(ticketer)?,
+                    Type::try_from(t)?,
+                    data(contents)?,
+                    data(amount)?,
+                )
+            }
+            H_constant => Data::Constant(constant(args)?),
+            _ => return unexpected("data", node),
+        })
+    }
+}
+
+fn elt(node: &Node) -> Option<(&Node, &Node)> {
+    match node {
+        Node::Prim {
+            prim: Primitive::D_Elt,
+            args,
+            ..
+        } => match args.as_slice() {
+            [k, v] => Some((k, v)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn data_seq(nodes: &[Node]) -> Result<Data, FromNodeError> {
+    if !nodes.is_empty() && nodes.iter().all(|node| elt(node).is_some()) {
+        return nodes
+            .iter()
+            .filter_map(elt)
+            .map(|(k, v)| Ok((Data::try_from(k)?, Data::try_from(v)?)))
+            .collect::<Result<_, _>>()
+            .map(Data::Map);
+    }
+    match nodes.iter().map(Data::try_from).collect() {
+        Ok(data) => Ok(Data::Seq(data)),
+        Err(data_error) => match nodes.iter().map(Instruction::try_from).collect() {
+            Ok(code) => Ok(Data::Lambda(code)),
+            Err(_) => Err(data_error),
+        },
+    }
+}
+
+impl From<&Data> for Node {
+    fn from(data: &Data) -> Self {
+        use Primitive::*;
+
+        match data {
+            Data::Int(i) => Node::Int(i.clone()),
+            Data::String(s) => Node::String(s.clone()),
+            Data::Bytes(b) => Node::Bytes(b.clone()),
+            Data::Unit => Node::prim(D_Unit, vec![]),
+            Data::Bool(true) => Node::prim(D_True, vec![]),
+            Data::Bool(false) => Node::prim(D_False, vec![]),
+            Data::Pair(ds) => Node::prim(D_Pair, ds.iter().map(Node::from).collect()),
+            Data::Left(d) => Node::prim(D_Left, vec![d.as_ref().into()]),
+            Data::Right(d) => Node::prim(D_Right, vec![d.as_ref().into()]),
+            Data::Some(d) => Node::prim(D_Some, vec![d.as_ref().into()]),
+            Data::None => Node::prim(D_None, vec![]),
+            Data::Seq(ds) => Node::seq(ds.iter().map(Node::from).collect()),
+            Data::Map(elts) => Node::seq(
+                elts.iter()
+                    .map(|(k, v)| Node::prim(D_Elt, vec![k.into(), v.into()]))
+                    .collect(),
+            ),
+            Data::Lambda(c) => seq(c),
+            Data::LambdaRec(c) => Node::prim(D_Lambda_rec, vec![seq(c)]),
+            Data::Ticket(ticketer, t, contents, amount) => Node::prim(
+                D_Ticket,
+                vec![
+                    ticketer.as_ref().into(),
+                    t.into(),
+                    contents.as_ref().into(),
+                    amount.as_ref().into(),
+                ],
+            ),
+            Data::Constant(hash) => Node::prim(H_constant, vec![Node::string(hash.as_str())]),
+        }
+    }
+}
+
+impl TryFrom<&Node> for Instruction {
+    type Error = FromNodeError;
+
+    fn try_from(node: &Node) -> Result<Self, Self::Error> {
+        use Primitive::*;
+
+        let (prim, args, annots) = match node {
+            Node::Seq(_) => return code(node).map(Instruction::Seq),
+            Node::Prim { prim, args, annots } => (*prim, args.as_slice(), annots),
+            _ => return unexpected("instruction", node),
+        };
+        let optional_nat = || match args {
+            [] => Ok(None),
+            _ => arity::<1>(prim, args).and_then(|[n]| small_nat(n).map(Some)),
+        };
+        Ok(match prim {
+            _ if SIMPLE_INSTRUCTIONS.contains(&prim) => {
+                arity::<0>(prim, args)?;
+                Instruction::Simple(prim)
+            }
+            I_DROP => Instruction::Drop(optional_nat()?),
+            I_DUP => Instruction::Dup(optional_nat()?),
+            I_PAIR => Instruction::Pair(optional_nat()?),
+            I_UNPAIR => Instruction::Unpair(optional_nat()?),
+            I_GET => Instruction::Get(optional_nat()?),
+            I_UPDATE => Instruction::Update(optional_nat()?),
+            I_DIG | I_DUG | I_SAPLING_EMPTY_STATE => {
+                let [n] = arity(prim, args)?;
+                let n = small_nat(n)?;
+                match prim {
+                    I_DIG => Instruction::Dig(n),
+                    I_DUG => Instruction::Dug(n),
+                    _ => Instruction::SaplingEmptyState(n),
+                }
+            }
+            I_DIP => match args {
+                [c] => Instruction::Dip(None, code(c)?),
+                _ => {
+                    let [n, c] = arity(prim, args)?;
+                    Instruction::Dip(Some(small_nat(n)?), code(c)?)
+                }
+            },
+            I_PUSH => {
+                let [t, d] = arity(prim, args)?;
+                Instruction::Push(Type::try_from(t)?, Data::try_from(d)?)
+            }
+            I_NIL | I_NONE | I_LEFT | I_RIGHT | I_EMPTY_SET | I_UNPACK | I_CAST | I_CONTRACT => {
+                let [t] = arity(prim, args)?;
+                let t = Type::try_from(t)?;
+                match prim {
+                    I_NIL => Instruction::Nil(t),
+                    I_NONE => Instruction::None(t),
+                    I_LEFT => Instruction::Left(t),
+                    I_RIGHT => Instruction::Right(t),
+                    I_EMPTY_SET => Instruction::EmptySet(t),
+                    I_UNPACK => Instruction::Unpack(t),
+                    I_CAST => Instruction::Cast(t),
+                    _ => Instruction::Contract(annotation(annots, '%'), t),
+                }
+            }
+            I_EMPTY_MAP | I_EMPTY_BIG_MAP => {
+                let [k, v] = arity(prim, args)?;
+                let (k, v) = (Type::try_from(k)?, Type::try_from(v)?);
+                match prim {
+                    I_EMPTY_MAP => Instruction::EmptyMap(k, v),
+                    _ => Instruction::EmptyBigMap(k, v),
+                }
+            }
+            I_SELF => {
+                arity::<0>(prim, args)?;
+                Instruction::SelfContract(annotation(annots, '%'))
+            }
+            I_EMIT => {
+                let t = match args {
+                    [] => None,
+                    _ => Some(arity::<1>(prim, args).and_then(|[t]| Type::try_from(t))?),
+                };
+                Instruction::Emit(annotation(annots, '%'), t)
+            }
+            I_VIEW => {
+                let [name, t] = arity(prim, args)?;
+                Instruction::View(string(name)?, Type::try_from(t)?)
+            }
+            I_LAMBDA | I_LAMBDA_REC => {
+                let [a, r, c] = arity(prim, args)?;
+                let (a, r, c) = (Type::try_from(a)?, Type::try_from(r)?, code(c)?);
+                match prim {
+                    I_LAMBDA => Instruction::Lambda(a, r, c),
+                    _ => Instruction::LambdaRec(a, r, c),
+                }
+            }
+            I_IF | I_IF_NONE | I_IF_LEFT | I_IF_CONS => {
+                let [t, f] = arity(prim, args)?;
+                let (t, f) = (code(t)?, code(f)?);
+                match prim {
+                    I_IF => Instruction::If(t, f),
+                    I_IF_NONE => Instruction::IfNone(t, f),
+                    I_IF_LEFT => Instruction::IfLeft(t, f),
+                    _ => Instruction::IfCons(t, f),
+                }
+            }
+            I_LOOP | I_LOOP_LEFT | I_ITER | I_MAP => {
+                let [c] = arity(prim, args)?;
+                let c = code(c)?;
+                match prim {
+                    I_LOOP => Instruction::Loop(c),
+                    I_LOOP_LEFT => Instruction::LoopLeft(c),
+                    I_ITER => Instruction::Iter(c),
+                    _ => Instruction::Map(c),
+                }
+            }
+            I_CREATE_CONTRACT => {
+                let [script] = arity(prim, args)?;
+                Instruction::CreateContract(Box::new(Script::try_from(script)?))
+            }
+            H_constant => Instruction::Constant(constant(args)?),
+            _ => return unexpected("instruction", node),
+        })
+    }
+}
+
+impl From<&Instruction> for Node {
+    fn from(instr: &Instruction) -> Self {
+        use Primitive::*;
+
+        let entrypoint = |e: &Option<String>| e.iter().map(|e| format!("%{}", e)).collect();
+        let optional_nat =
+            |prim, n: &Option<u16>| Node::prim(prim, n.iter().map(|n| int(*n)).collect());
+        match instr {
+            Instruction::Simple(prim) => Node::prim(*prim, vec![]),
+            Instruction::Seq(c) => seq(c),
+            Instruction::Drop(n) => optional_nat(I_DROP, n),
+            Instruction::Dup(n) => optional_nat(I_DUP, n),
+            Instruction::Pair(n) => optional_nat(I_PAIR, n),
+            Instruction::Unpair(n) => optional_nat(I_UNPAIR, n),
+            Instruction::Get(n) => optional_nat(I_GET, n),
+            Instruction::Update(n) => optional_nat(I_UPDATE, n),
+            Instruction::Dig(n) => Node::prim(I_DIG, vec![int(*n)]),
+            Instruction::Dug(n) => Node::prim(I_DUG, vec![int(*n)]),
+            Instruction::SaplingEmptyState(n) => Node::prim(I_SAPLING_EMPTY_STATE, vec![int(*n)]),
+            Instruction::Dip(n, c) => {
+                let mut args = n.iter().map(|n| int(*n)).collect::<Vec<_>>();
+                args.push(seq(c));
+                Node::prim(I_DIP, args)
+            }
+            Instruction::Push(t, d) => Node::prim(I_PUSH, vec![t.into(), d.into()]),
+            Instruction::Nil(t) => Node::prim(I_NIL, vec![t.into()]),
+            Instruction::None(t) => Node::prim(I_NONE, vec![t.into()]),
+            Instruction::Left(t) => Node::prim(I_LEFT, vec![t.into()]),
+            Instruction::Right(t) => Node::prim(I_RIGHT, vec![t.into()]),
+            Instruction::EmptySet(t) => Node::prim(I_EMPTY_SET, vec![t.into()]),
+            Instruction::Unpack(t) => Node::prim(I_UNPACK, vec![t.into()]),
+            Instruction::Cast(t) => Node::prim(I_CAST, vec![t.into()]),
+            Instruction::EmptyMap(k, v) => Node::prim(I_EMPTY_MAP, vec![k.into(), v.into()]),
+            Instruction::EmptyBigMap(k, v) => Node::prim(I_EMPTY_BIG_MAP, vec![k.into(), v.into()]),
+            Instruction::Contract(e, t) => {
+                Node::prim_annot(I_CONTRACT, vec![t.into()], entrypoint(e))
+            }
+            Instruction::SelfContract(e) => Node::prim_annot(I_SELF, vec![], entrypoint(e)),
+            Instruction::Emit(tag, t) => {
+                Node::prim_annot(I_EMIT, t.iter().map(Node::from).collect(), entrypoint(tag))
+            }
+            Instruction::View(name, t) => {
+                Node::prim(I_VIEW, vec![Node::string(name.as_str()), t.into()])
+            }
+            Instruction::Lambda(a, r, c) => Node::prim(I_LAMBDA, vec![a.into(), r.into(), seq(c)]),
+            Instruction::LambdaRec(a, r, c) => {
+                Node::prim(I_LAMBDA_REC, vec![a.into(), r.into(), seq(c)])
+            }
+            Instruction::If(t, f) => Node::prim(I_IF, vec![seq(t), seq(f)]),
+            Instruction::IfNone(t, f) => Node::prim(I_IF_NONE, vec![seq(t), seq(f)]),
+            Instruction::IfLeft(t, f) => Node::prim(I_IF_LEFT, vec![seq(t), seq(f)]),
+            Instruction::IfCons(t, f) => Node::prim(I_IF_CONS, vec![seq(t), seq(f)]),
+            Instruction::Loop(c) => Node::prim(I_LOOP, vec![seq(c)]),
+            Instruction::LoopLeft(c) => Node::prim(I_LOOP_LEFT, vec![seq(c)]),
+            Instruction::Iter(c) => Node::prim(I_ITER, vec![seq(c)]),
+            Instruction::Map(c) => Node::prim(I_MAP, vec![seq(c)]),
+            Instruction::CreateContract(script) => {
+                Node::prim(I_CREATE_CONTRACT, vec![script.as_ref().into()])
+            }
+            Instruction::Constant(hash) => {
+                Node::prim(H_constant, vec![Node::string(hash.as_str())])
+            }
+        }
+    }
+}
+
+impl TryFrom<&Node> for Script {
+    type Error = FromNodeError;
+
+    fn try_from(node: &Node) -> Result<Self, Self::Error> {
+        use Primitive::*;
+
+        let sections = match node {
+            Node::Seq(sections) => sections,
+            _ => return unexpected("script", node),
+        };
+        let (mut parameter, mut storage, mut code_section) = (None, None, None);
+        let mut views = Vec::new();
+        for section in sections {
+            let (prim, args) = match section {
+                Node::Prim { prim, args, .. } => (*prim, args.as_slice()),
+                _ => return unexpected("script section", section),
+            };
+            let duplicate = match prim {
+                K_parameter => {
+                    let [t] = arity(prim, args)?;
+                    parameter.replace(Type::try_from(t)?).is_some()
+                }
+                K_storage => {
+                    let [t] = arity(prim, args)?;
+                    storage.replace(Type::try_from(t)?).is_some()
+                }
+                K_code => {
+                    let [c] = arity(prim, args)?;
+                    code_section.replace(code(c)?).is_some()
+                }
+                K_view => {
+                    let [name, input, output, c] = arity(prim, args)?;
+                    views.push(View {
+                        name: string(name)?,
+                        input: Type::try_from(input)?,
+                        output: Type::try_from(output)?,
+                        code: code(c)?,
+                    });
+                    false
+                }
+                _ => return unexpected("script section", section),
+            };
+            if duplicate {
+                return Err(FromNodeError::Section(prim));
+            }
+        }
+        Ok(Script {
+            parameter: parameter.ok_or(FromNodeError::Section(K_parameter))?,
+            storage: storage.ok_or(FromNodeError::Section(K_storage))?,
+            code: code_section.ok_or(FromNodeError::Section(K_code))?,
+            views,
+        })
+    }
+}
+
+impl From<&Script> for Node {
+    fn from(script: &Script) -> Self {
+        use Primitive::*;
+
+        let mut sections = vec![
+            Node::prim(K_parameter, vec![(&script.parameter).into()]),
+            Node::prim(K_storage, vec![(&script.storage).into()]),
+            Node::prim(K_code, vec![seq(&script.code)]),
+        ];
+        sections.extend(script.views.iter().map(|view| {
+            Node::prim(
+                K_view,
+                vec![
+                    Node::string(view.name.as_str()),
+                    (&view.input).into(),
+                    (&view.output).into(),
+                    seq(&view.code),
+                ],
+            )
+        }));
+        Node::seq(sections)
+    }
+}
+
+macro_rules! display_as_node {
+    ($($ty:ty),*) => {
+        $(
+            impl fmt::Display for $ty {
+                /// Formats in Michelson concrete syntax, on a single line, or on several
+                /// lines with `{:#}`.
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    Node::from(self).fmt(f)
+                }
+            }
+        )*
+    };
+}
+
+display_as_node!(Type, Data, Instruction, Script);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Primitive::*;
+
+    fn prim(prim: Primitive, args: Vec<Node>) -> Node {
+        Node::prim(prim, args)
+    }
+
+    fn field(name: &str, prim: Primitive) -> Node {
+        Node::prim_annot(prim, vec![], vec![format!("%{}", name)])
+    }
+
+    fn round_trip<T>(node: Node, expected: T)
+    where
+        T: for<'a> TryFrom<&'a Node, Error = FromNodeError> + fmt::Debug + PartialEq,
+        for<'a> &'a T: Into<Node>,
+    {
+        let typed = T::try_from(&node).unwrap();
+        assert_eq!(typed, expected);
+        assert_eq!((&typed).into(), node);
+    }
+
+    #[test]
+    fn types() {
+        round_trip(prim(T_nat, vec![]), Type::Nat);
+        round_trip(
+            prim(
+                T_or,
+                vec![field("deposit", T_unit), field("withdraw", T_mutez)],
+            ),
+            Type::Or(
+                Box::new(Type::Field("deposit".into(), Box::new(Type::Unit))),
+                Box::new(Type::Field("withdraw".into(), Box::new(Type::Mutez))),
+            ),
+        );
+        round_trip(
+            prim(
+                T_pair,
+                vec![
+                    prim(T_int, vec![]),
+                    prim(
+                        T_big_map,
+                        vec![prim(T_address, vec![]), prim(T_nat, vec![])],
+                    ),
+                    prim(T_sapling_state, vec![Node::int(8)]),
+                ],
+            ),
+            Type::Pair(vec![
+                Type::Int,
+                Type::BigMap(Box::new(Type::Address), Box::new(Type::Nat)),
+                Type::SaplingState(8),
+            ]),
+        );
+
+        // only field annotations are kept
+        let annotated = Node::prim_annot(T_nat, vec![], vec![":t".into(), "%f".into()]);
+        assert_eq!(
+            Type::try_from(&annotated),
+            Ok(Type::Field("f".into(), Box::new(Type::Nat)))
+        );
+    }
+
+    #[test]
+    fn data() {
+        round_trip(
+            prim(
+                D_Pair,
+                vec![Node::int(1), Node::string("a"), prim(D_Unit, vec![])],
+            ),
+            Data::Pair(vec![
+                Data::Int(1.into()),
+                Data::String("a".into()),
+                Data::Unit,
+            ]),
+        );
+        round_trip(
+            Node::seq(vec![prim(D_Elt, vec![Node::int(1), prim(D_True, vec![])])]),
+            Data::Map(vec![(Data::Int(1.into()), Data::Bool(true))]),
+        );
+        round_trip(
+            Node::seq(vec![
+                prim(D_Some, vec![Node::bytes(vec![1])]),
+                prim(D_None, vec![]),
+            ]),
+            Data::Seq(vec![Data::Some(Box::new(Data::Bytes(vec![1]))), Data::None]),
+        );
+        round_trip(Node::seq(vec![]), Data::Seq(vec![]));
+        round_trip(
+            Node::seq(vec![prim(I_DROP, vec![]), prim(I_UNIT, vec![])]),
+            Data::Lambda(vec![Instruction::Drop(None), Instruction::Simple(I_UNIT)]),
+        );
+        round_trip(
+            prim(
+                D_Ticket,
+                vec![
+                    Node::string("KT1ThEdxfUcWUwqsdergy3QnbCWGHSUHeHJq"),
+                    prim(T_nat, vec![]),
+                    Node::int(1),
+                    Node::int(10),
+                ],
+            ),
+            Data::Ticket(
+                Box::new(Data::String("KT1ThEdxfUcWUwqsdergy3QnbCWGHSUHeHJq".into())),
+                Type::Nat,
+                Box::new(Data::Int(1.into())),
+                Box::new(Data::Int(10.into())),
+            ),
+        );
+    }
+
+    #[test]
+    fn instructions() {
+        round_trip(
+            Node::seq(vec![
+                prim(I_DUP, vec![Node::int(2)]),
+                prim(
+                    I_DIP,
+                    vec![Node::int(1), Node::seq(vec![prim(I_DROP, vec![])])],
+                ),
+                prim(I_PUSH, vec![prim(T_nat, vec![]), Node::int(1)]),
+                prim(
+                    I_IF_LEFT,
+                    vec![Node::seq(vec![prim(I_ADD, vec![])]), Node::seq(vec![])],
+                ),
+                Node::prim_annot(I_CONTRACT, vec![prim(T_unit, vec![])], vec!["%a".into()]),
+                Node::prim_annot(I_SELF, vec![], vec![]),
+            ]),
+            Instruction::Seq(vec![
+                Instruction::Dup(Some(2)),
+                Instruction::Dip(Some(1), vec![Instruction::Drop(None)]),
+                Instruction::Push(Type::Nat, Data::Int(1.into())),
+                Instruction::IfLeft(vec![Instruction::Simple(I_ADD)], vec![]),
+                Instruction::Contract(Some("a".into()), Type::Unit),
+                Instruction::SelfContract(None),
+            ]),
+        );
+    }
+
+    #[test]
+    fn script() {
+        let node = Node::seq(vec![
+            prim(K_parameter, vec![prim(T_unit, vec![])]),
+            prim(K_storage, vec![prim(T_nat, vec![])]),
+            prim(
+                K_code,
+                vec![Node::seq(vec![
+                    prim(I_CDR, vec![]),
+                    prim(I_NIL, vec![prim(T_operation, vec![])]),
+                    prim(I_PAIR, vec![]),
+                ])],
+            ),
+            prim(
+                K_view,
+                vec![
+                    Node::string("get"),
+                    prim(T_unit, vec![]),
+                    prim(T_nat, vec![]),
+                    Node::seq(vec![prim(I_CDR, vec![])]),
+                ],
+            ),
+        ]);
+        let script = Script {
+            parameter: Type::Unit,
+            storage: Type::Nat,
+            code: vec![
+                Instruction::Simple(I_CDR),
+                Instruction::Nil(Type::Operation),
+                Instruction::Pair(None),
+            ],
+            views: vec![View {
+                name: "get".into(),
+                input: Type::Unit,
+                output: Type::Nat,
+                code: vec![Instruction::Simple(I_CDR)],
+            }],
+        };
+        assert_eq!(
+            script.to_string(),
+            r#"{ parameter unit ; storage nat ; code { CDR ; NIL operation ; PAIR } ; view "get" unit nat { CDR } }"#
+        );
+        assert_eq!(
+            format!("{:#}", script),
+            r#"{ parameter unit ;
+  storage nat ;
+  code { CDR ;
+         NIL operation ;
+         PAIR } ;
+  view "get" unit nat { CDR } }"#
+        );
+        round_trip(node, script);
+    }
+
+    #[test]
+    fn constants() {
+        let hash = "exprtZBwZUeYYYfUs9B9Rg2ywHezVHnCCnmF9WsDQVrs582dSK63dC";
+        let constant = || prim(H_constant, vec![Node::string(hash)]);
+        round_trip(constant(), Type::Constant(hash.into()));
+        round_trip(constant(), Data::Constant(hash.into()));
+        round_trip(constant(), Instruction::Constant(hash.into()));
+        assert_eq!(
+            Type::try_from(&prim(H_constant, vec![Node::int(0)])),
+            Err(FromNodeError::Unexpected {
+                expected: "string",
+                found: "integer".into()
+            })
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            Type::try_from(&prim(I_ADD, vec![])),
+            Err(FromNodeError::Unexpected {
+                expected: "type",
+                found: "`ADD`".into()
+            })
+        );
+        assert_eq!(
+            Type::try_from(&prim(T_pair, vec![prim(T_nat, vec![])])),
+            Err(FromNodeError::Arity {
+                prim: T_pair,
+                count: 1
+            })
+        );
+        assert_eq!(
+            Instruction::try_from(&prim(I_DIG, vec![Node::int(1 << 16)])),
+            Err(FromNodeError::OutOfRange((1 << 16).into()))
+        );
+        assert_eq!(
+            Instruction::try_from(&prim(I_IF, vec![prim(I_ADD, vec![]), Node::seq(vec![])])),
+            Err(FromNodeError::Unexpected {
+                expected: "sequence of instructions",
+                found: "`ADD`".into()
+            })
+        );
+        assert_eq!(
+            Data::try_from(&Node::seq(vec![prim(T_nat, vec![])])),
+            Err(FromNodeError::Unexpected {
+                expected: "data",
+                found: "`nat`".into()
+            })
+        );
+        let storage = prim(K_storage, vec![prim(T_nat, vec![])]);
+        assert_eq!(
+            Script::try_from(&Node::seq(vec![storage.clone(), storage])),
+            Err(FromNodeError::Section(K_storage))
+        );
+        assert_eq!(
+            Script::try_from(&Node::seq(vec![])),
+            Err(FromNodeError::Section(K_parameter))
+        );
+    }
+}